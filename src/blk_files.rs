@@ -0,0 +1,219 @@
+use crate::{BlockHeader, BlockReader, BpuError, Network, ParseConfig, Result, BLOCK_HEADER_SIZE};
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::{self, BufReader, Read, Seek, SeekFrom, Take};
+use std::path::{Path, PathBuf};
+
+/// A block stored in a `blk*.dat` file
+#[derive(Debug, Clone)]
+pub struct BlkRecord {
+    pub path: PathBuf,
+    /// Offset of the serialized block (after the magic and size prefix)
+    pub offset: u64,
+    pub size: u64,
+    pub header: BlockHeader,
+}
+
+/// Reads the node's `blocks/blk*.dat` files, where each block is stored as
+/// network magic, a little-endian u32 size and the serialized block.
+/// Blocks of 4 GiB or more have a u32 size of `0xffffffff`, followed by their u64 size.
+#[derive(Debug, Clone)]
+pub struct BlkFiles {
+    pub dir: PathBuf,
+    pub network: Network,
+}
+
+impl BlkFiles {
+    pub fn new(dir: impl Into<PathBuf>, network: Network) -> Self {
+        Self {
+            dir: dir.into(),
+            network,
+        }
+    }
+
+    /// `blk*.dat` files in the directory, in file number order
+    pub fn paths(&self) -> Result<Vec<PathBuf>> {
+        let mut paths = vec![];
+
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            let name = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .unwrap_or_default();
+
+            if name.starts_with("blk") && name.ends_with(".dat") {
+                paths.push(path);
+            }
+        }
+
+        paths.sort();
+        Ok(paths)
+    }
+
+    /// Every block in file order, reading only the headers
+    pub fn records(&self) -> Result<Vec<BlkRecord>> {
+        let mut records = vec![];
+
+        for path in self.paths()? {
+            records.extend(Self::file_records(&path, self.network)?);
+        }
+
+        Ok(records)
+    }
+
+    /// Blocks of the longest prev-hash chain, with their heights.
+    ///
+    /// The chain starts at the block whose parent is not in the files (the genesis block,
+    /// or the first stored block when the files start later, at `start_height`).
+    /// Blocks off the longest chain (stale blocks) are left out.
+    pub fn records_by_height(&self, start_height: u32) -> Result<Vec<(u32, BlkRecord)>> {
+        let records = self.records()?;
+
+        let hashes: HashSet<&str> = records
+            .iter()
+            .map(|record| record.header.hash.as_str())
+            .collect();
+        let mut children: HashMap<&str, Vec<usize>> = HashMap::new();
+        let mut roots = vec![];
+
+        for (index, record) in records.iter().enumerate() {
+            match hashes.contains(record.header.prev_hash.as_str()) {
+                true => children
+                    .entry(record.header.prev_hash.as_str())
+                    .or_default()
+                    .push(index),
+                false => roots.push(index),
+            }
+        }
+
+        // Depth of every block below a root, keeping the parent of each block
+        let mut parents: HashMap<usize, usize> = HashMap::new();
+        let mut tip = None;
+        let mut stack: Vec<(usize, u32)> = roots.into_iter().map(|root| (root, 0)).collect();
+
+        while let Some((index, depth)) = stack.pop() {
+            if !matches!(tip, Some((_, best)) if depth <= best) {
+                tip = Some((index, depth));
+            }
+
+            for child in children
+                .get(records[index].header.hash.as_str())
+                .into_iter()
+                .flatten()
+            {
+                parents.insert(*child, index);
+                stack.push((*child, depth + 1));
+            }
+        }
+
+        let mut chain = vec![];
+        let mut next = tip.map(|(index, _)| index);
+        while let Some(index) = next {
+            chain.push(index);
+            next = parents.get(&index).copied();
+        }
+        chain.reverse();
+
+        Ok(chain
+            .into_iter()
+            .enumerate()
+            .map(|(height, index)| (start_height + height as u32, records[index].clone()))
+            .collect())
+    }
+
+    /// Opens a stored block for streaming its transactions
+    pub fn open(
+        &self,
+        record: &BlkRecord,
+        height: u32,
+        parse_config: ParseConfig,
+    ) -> Result<BlockReader<Take<BufReader<File>>>> {
+        let mut file = File::open(&record.path)?;
+        file.seek(SeekFrom::Start(record.offset))?;
+
+        BlockReader::new(BufReader::new(file).take(record.size), height, parse_config)
+    }
+
+    /// Blocks of the longest chain in height order (see `records_by_height`),
+    /// each streaming its transactions
+    pub fn blocks_by_height(
+        &self,
+        start_height: u32,
+        parse_config: ParseConfig,
+    ) -> Result<impl Iterator<Item = Result<BlockReader<Take<BufReader<File>>>>> + '_> {
+        let records = self.records_by_height(start_height)?;
+
+        Ok(records
+            .into_iter()
+            .map(move |(height, record)| self.open(&record, height, parse_config.clone())))
+    }
+
+    /// Blocks stored in one file, reading the headers and seeking past the rest
+    pub fn file_records(path: &Path, network: Network) -> Result<Vec<BlkRecord>> {
+        let mut file = BufReader::new(File::open(path)?);
+        let len = file.get_ref().metadata()?.len();
+        let mut records = vec![];
+
+        loop {
+            let position = file.stream_position()?;
+            if position + 8 > len {
+                break;
+            }
+
+            let mut prefix = [0u8; 8];
+            file.read_exact(&mut prefix)?;
+
+            // Files are preallocated, so the data ends at the first zeroed prefix
+            if prefix[..4] == [0u8; 4] {
+                break;
+            }
+
+            if prefix[..4] != network.disk_magic() {
+                return Err(BpuError::CustomError(format!(
+                    "Unexpected magic {} at offset {} of {}",
+                    hex::encode(&prefix[..4]),
+                    position,
+                    path.display()
+                )));
+            }
+
+            let (size, offset) = match u32::from_le_bytes(prefix[4..].try_into().unwrap()) {
+                u32::MAX if position + 16 <= len => {
+                    let mut size = [0u8; 8];
+                    file.read_exact(&mut size)?;
+                    (u64::from_le_bytes(size), position + 16)
+                }
+                size => (size as u64, position + 8),
+            };
+
+            if size < BLOCK_HEADER_SIZE as u64
+                || offset.checked_add(size).map_or(true, |end| end > len)
+            {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    format!(
+                        "truncated block at offset {} of {}",
+                        position,
+                        path.display()
+                    ),
+                )
+                .into());
+            }
+
+            let mut header = [0u8; BLOCK_HEADER_SIZE];
+            file.read_exact(&mut header)?;
+
+            records.push(BlkRecord {
+                path: path.to_path_buf(),
+                offset,
+                size,
+                header: BlockHeader::from_bytes(&header)?,
+            });
+
+            file.seek(SeekFrom::Start(offset + size))?;
+        }
+
+        Ok(records)
+    }
+}
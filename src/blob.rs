@@ -0,0 +1,71 @@
+use crate::Result;
+use bsv::Hash;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// Makes temporary file names unique across the threads of this process
+static TMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Content-addressed storage for large pushes (like BitFS for Planaria)
+pub trait BlobStore: Send + Sync {
+    /// Stores `bytes` and returns their SHA-256 hash (hex)
+    fn put(&self, bytes: &[u8]) -> Result<String>;
+}
+
+impl std::fmt::Debug for dyn BlobStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("BlobStore")
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Offloads pushes over `limit` bytes to `store`, referencing them by hash in `f`/`lf`
+pub struct BlobConfig {
+    pub store: Arc<dyn BlobStore>,
+    pub limit: usize,
+}
+
+#[derive(Debug, Clone)]
+/// Stores each blob as `<root>/<sha256>`
+pub struct FsBlobStore {
+    pub root: PathBuf,
+}
+
+impl FsBlobStore {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+}
+
+impl BlobStore for FsBlobStore {
+    fn put(&self, bytes: &[u8]) -> Result<String> {
+        let hash = Hash::sha_256(bytes).to_hex();
+        let path = self.root.join(&hash);
+
+        // Content-addressed, so an existing file already holds these bytes
+        if !path.exists() {
+            fs::create_dir_all(&self.root)?;
+
+            let tmp = self.root.join(format!(
+                "{}.{}.{}.tmp",
+                hash,
+                std::process::id(),
+                TMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+            ));
+            fs::write(&tmp, bytes)?;
+
+            if let Err(e) = fs::rename(&tmp, &path) {
+                let _ = fs::remove_file(&tmp);
+
+                // Another writer stored the same blob first
+                if !path.exists() {
+                    return Err(e.into());
+                }
+            }
+        }
+
+        Ok(hash)
+    }
+}
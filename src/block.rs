@@ -0,0 +1,160 @@
+use crate::bpu_ref::{Cursor, TxidRef};
+use crate::reader::{read_raw_tx, read_varint};
+use crate::{Block, Bob, BobTx, BpuError, ParseConfig, Result, BPU};
+use bsv::{Hash, Transaction};
+use serde::{Deserialize, Serialize};
+use std::io::Read;
+
+/// Size of a serialized block header
+pub const BLOCK_HEADER_SIZE: usize = 80;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+/// Block header fields, with hashes as display hex
+pub struct BlockHeader {
+    pub hash: String,
+    pub version: u32,
+    pub prev_hash: String,
+    pub merkle_root: String,
+    pub time: u32,
+    pub bits: u32,
+    pub nonce: u32,
+}
+
+impl BlockHeader {
+    /// Parses a serialized 80-byte block header
+    pub fn from_bytes(header: &[u8]) -> Result<BlockHeader> {
+        if header.len() != BLOCK_HEADER_SIZE {
+            return Err(BpuError::CustomError(format!(
+                "Block header must be {} bytes, got {}",
+                BLOCK_HEADER_SIZE,
+                header.len()
+            )));
+        }
+
+        let mut cursor = Cursor::new(header);
+
+        Ok(BlockHeader {
+            hash: TxidRef(&Hash::sha_256d(header).to_bytes()).to_hex(),
+            version: cursor.u32()?,
+            prev_hash: TxidRef(cursor.take(32)?).to_hex(),
+            merkle_root: TxidRef(cursor.take(32)?).to_hex(),
+            time: cursor.u32()?,
+            bits: cursor.u32()?,
+            nonce: cursor.u32()?,
+        })
+    }
+
+    pub fn from_hex(header: &str) -> Result<BlockHeader> {
+        let bytes = hex::decode(header).map_err(|err| BpuError::CustomError(err.to_string()))?;
+        Self::from_bytes(&bytes)
+    }
+
+    /// Block Info for this header at `height`
+    pub fn block(&self, height: u32) -> Block {
+        Block::new(height, self.time, Some(self.hash.clone())).with_header(self.clone())
+    }
+}
+
+/// Parses every transaction of a serialized block at `height`.
+/// Results keep every BPU field; use `BlockReader::bob` for BOB indexer output.
+pub fn parse_block(raw: &[u8], height: u32, parse_config: &ParseConfig) -> Result<Vec<BobTx>> {
+    let mut reader = raw;
+    let txs =
+        BlockReader::new(&mut reader, height, parse_config.clone())?.collect::<Result<_>>()?;
+
+    if !reader.is_empty() {
+        return Err(BpuError::CustomError(
+            "Unexpected trailing bytes after block".to_string(),
+        ));
+    }
+
+    Ok(txs)
+}
+
+/// Streams the transactions of a serialized block, reading one transaction at a time
+/// so that the whole block is never held in memory
+pub struct BlockReader<R: Read> {
+    reader: R,
+    header: BlockHeader,
+    height: u32,
+    tx_count: u64,
+    position: u64,
+    parse_config: ParseConfig,
+    /// Shapes results like `Bob::from_bpu`
+    bob: bool,
+}
+
+impl<R: Read> BlockReader<R> {
+    /// Reads the block header and transaction count from `reader`.
+    /// Results keep every BPU field (version, locktime, sequence numbers, script types
+    /// and address key material), unlike `Bob::from_bpu`.
+    pub fn new(mut reader: R, height: u32, parse_config: ParseConfig) -> Result<Self> {
+        let mut header = [0u8; BLOCK_HEADER_SIZE];
+        reader.read_exact(&mut header)?;
+        let header = BlockHeader::from_bytes(&header)?;
+        let tx_count = read_varint(&mut reader)?;
+
+        Ok(Self {
+            reader,
+            header,
+            height,
+            tx_count,
+            position: 0,
+            parse_config,
+            bob: false,
+        })
+    }
+
+    /// Reads with `Bob::config()`, yielding the same BobTxs as `Bob::from_bytes`
+    pub fn bob(reader: R, height: u32) -> Result<Self> {
+        Ok(Self {
+            bob: true,
+            ..Self::new(reader, height, Bob::config())?
+        })
+    }
+
+    pub fn header(&self) -> &BlockHeader {
+        &self.header
+    }
+
+    pub fn tx_count(&self) -> u64 {
+        self.tx_count
+    }
+
+    /// Block Info of the block being read (w/o position)
+    pub fn block(&self) -> Block {
+        self.header.block(self.height)
+    }
+
+    fn read_tx(&mut self) -> Result<BobTx> {
+        let raw_tx = read_raw_tx(&mut self.reader)?;
+        let tx = Transaction::from_bytes(&raw_tx)?;
+        let bpu = BPU::collect(&tx, &self.parse_config)?;
+
+        let blk = self.block().with_position(self.position as u32);
+        Ok(match self.bob {
+            true => Bob::from_bpu(bpu, Some(blk), None),
+            false => BobTx::new(bpu, Some(blk), None),
+        })
+    }
+}
+
+impl<R: Read> Iterator for BlockReader<R> {
+    type Item = Result<BobTx>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.position >= self.tx_count {
+            return None;
+        }
+
+        let result = self.read_tx();
+        self.position += 1;
+
+        // Stop after an error, the stream position may no longer be at a transaction
+        if result.is_err() {
+            self.position = self.tx_count;
+        }
+
+        Some(result)
+    }
+}
@@ -1,5 +1,8 @@
 use crate::components::*;
-use bsv::{OpCodes, P2PKHAddress, PublicKey, ScriptBit, Transaction, TxIn, TxOut};
+use crate::encode::collect_cells;
+use crate::reader::read_raw_tx;
+use crate::script_type::{AddressKey, ScriptTemplate};
+use bsv::{OpCodes, ScriptBit, Transaction, TxIn, TxOut};
 use serde::{Deserialize, Serialize};
 use serde_with::{serde_as, skip_serializing_none};
 
@@ -12,6 +15,7 @@ pub struct BPU {
     pub r#in: Vec<IO>,
     pub tx: Tx,
     pub lock: Option<u32>,
+    pub stats: Option<TxStats>,
 }
 
 impl std::fmt::Display for BPU {
@@ -23,10 +27,10 @@ impl std::fmt::Display for BPU {
 pub fn maybe_transform(
     cell: Cell,
     script_bit: &ScriptBit,
-    transform: Option<fn(Cell, &ScriptBit) -> Cell>,
+    transform: Option<&dyn CellTransform>,
 ) -> Cell {
     match transform {
-        Some(f) => f(cell, script_bit),
+        Some(f) => f.transform(cell, script_bit),
         None => cell,
     }
 }
@@ -34,19 +38,55 @@ pub fn maybe_transform(
 impl BPU {
     pub fn from_raw_tx(raw_tx: &str, parse_config: ParseConfig) -> Result<BPU> {
         let gene = Transaction::from_hex(raw_tx)?;
-        Self::collect(&gene, parse_config) //settings, transform)
+        Self::collect(&gene, &parse_config)
+    }
+
+    pub fn from_bytes(raw_tx: &[u8], parse_config: ParseConfig) -> Result<BPU> {
+        let gene = Transaction::from_bytes(raw_tx)?;
+        Self::collect(&gene, &parse_config)
+    }
+
+    /// Reads one serialized transaction from `reader`
+    pub fn from_reader<R: std::io::Read>(reader: &mut R, parse_config: ParseConfig) -> Result<BPU> {
+        let raw_tx = read_raw_tx(reader)?;
+        Self::from_bytes(&raw_tx, parse_config)
+    }
+
+    /// Parses many raw transactions with one shared config (in parallel with the `rayon` feature).
+    /// Results are returned in input order, with a separate error for each transaction.
+    pub fn from_bytes_batch<T>(raw_txs: &[T], parse_config: &ParseConfig) -> Vec<Result<BPU>>
+    where
+        T: AsRef<[u8]> + Sync,
+    {
+        let parse = |raw_tx: &T| -> Result<BPU> {
+            let gene = Transaction::from_bytes(raw_tx.as_ref())?;
+            Self::collect(&gene, parse_config)
+        };
+
+        #[cfg(feature = "rayon")]
+        {
+            use rayon::prelude::*;
+            raw_txs.par_iter().map(parse).collect()
+        }
+
+        #[cfg(not(feature = "rayon"))]
+        {
+            raw_txs.iter().map(parse).collect()
+        }
     }
 
     /// Collects script bits from each Input/Output script
-    pub fn collect(tx: &Transaction, parse_config: ParseConfig) -> Result<BPU> {
+    pub fn collect(tx: &Transaction, parse_config: &ParseConfig) -> Result<BPU> {
         let mut results = BPU {
             tx: Tx {
                 h: tx.get_id_hex().ok(),
+                v: Some(tx.get_version()),
                 ..Default::default()
             },
             r#in: vec![],
             r#out: vec![],
             lock: Some(tx.get_n_locktime()),
+            stats: None,
         };
 
         let inputs: Vec<TxIn> = (0..tx.get_ninputs())
@@ -57,13 +97,10 @@ impl BPU {
             .map(|e| tx.get_output(e).unwrap())
             .collect();
 
-        let settings = parse_config.split;
-
         for (i, input) in inputs.iter().enumerate() {
             let mut limb = IO::new(i);
             let mut counter = IndexCounter::new(i);
             let mut cell: Vec<Cell> = vec![];
-            let mut address: Option<String> = None;
 
             let script = if let Ok(s) = input.get_finalised_script() {
                 s
@@ -71,57 +108,78 @@ impl BPU {
                 input.get_unlocking_script()
             };
 
-            if let Some(ScriptBit::Push(buf)) = script.get_script_bit(1) {
+            let prev_tx_id = input.get_prev_tx_id_hex(None);
+            let prev_out = match &parse_config.prev_outs {
+                Some(provider) => provider.prev_out(&prev_tx_id, input.get_vout())?,
+                None => None,
+            };
+
+            // The spent output gives the exact address, otherwise guess from the unlocking script
+            let key = match (&prev_out, script.get_script_bit(1)) {
+                (Some(prev_out), _) => {
+                    let prev_bits: Vec<ScriptBit> = prev_out.script.clone().into_iter().collect();
+                    ScriptTemplate::detect(&prev_bits).key()
+                }
                 // Public Key
-                if buf.len() == 33 && (buf[0] == 2 || buf[0] == 3) {
-                    if let Ok(v) = PublicKey::from_bytes(&buf) {
-                        if let Ok(v) = v.to_p2pkh_address() {
-                            if let Ok(v) = v.to_string() {
-                                address = Some(v)
-                            }
-                        }
-                    }
-                } else if buf.len() == 20 {
-                    // P2PKH
-                    if let Ok(v) = P2PKHAddress::from_pubkey_hash(&buf) {
-                        if let Ok(v) = v.to_string() {
-                            address = Some(v)
-                        }
-                    }
+                (None, Some(ScriptBit::Push(buf)))
+                    if buf.len() == 33 && (buf[0] == 2 || buf[0] == 3) =>
+                {
+                    AddressKey::from_pubkey(&buf)
                 }
-            }
+                // P2PKH
+                (None, Some(ScriptBit::Push(buf))) if buf.len() == 20 => {
+                    AddressKey::from_hash160(&buf)
+                }
+                _ => None,
+            };
 
-            let bits: Vec<ScriptBit> = script
-                .into_iter()
-                .flat_map(|e| flatten_script_bits(&[e]))
-                .collect();
+            let bits: Vec<ScriptBit> = match parse_config.nested {
+                true => script.into_iter().collect(),
+                false => script
+                    .into_iter()
+                    .flat_map(|e| flatten_script_bits(&[e]))
+                    .collect(),
+            };
 
-            for (i, chunk) in bits.iter().enumerate() {
-                counter.chunk_index = i;
+            let settings: Vec<SplitConfig> = parse_config
+                .split
+                .iter()
+                .filter(|setting| setting.applies_to(true, i))
+                .cloned()
+                .collect();
 
+            for chunk in bits.iter() {
                 Self::extract_cells(
                     &mut limb,
                     &mut cell,
                     &mut counter,
                     chunk,
                     &settings,
-                    parse_config.transform,
-                );
+                    parse_config,
+                )?;
             }
 
             if !cell.is_empty() {
                 limb.tape.push(Tape {
                     cell,
                     i: counter.tape_index,
+                    protocol: None,
                 });
             }
 
-            let sender = SendRecv {
-                h: Some(input.get_prev_tx_id_hex(None)),
+            limb.chunks = Self::trailing_chunks(&limb, &counter);
+
+            if let Some(registry) = &parse_config.bitcom {
+                registry.label(&mut limb.tape);
+            }
+
+            let mut sender = SendRecv {
+                h: Some(prev_tx_id),
                 i: input.get_vout(),
-                a: Some(address.unwrap_or("false".to_string())),
-                v: None,
+                v: prev_out.map(|prev_out| prev_out.satoshis),
+                ..Default::default()
             };
+            sender.set_address(key, parse_config);
 
             limb.e = Some(sender);
 
@@ -134,307 +192,372 @@ impl BPU {
             let mut limb = IO::new(i);
             let mut counter = IndexCounter::new(i);
             let mut cell: Vec<Cell> = vec![];
-            let mut address: Option<String> = None;
 
-            let script = output.get_script_pub_key();
+            let script_bits: Vec<ScriptBit> = output.get_script_pub_key().into_iter().collect();
 
-            if let Some(ScriptBit::Push(buf)) = script.get_script_bit(2) {
-                // P2PKH Script
-                if let Ok(v) = P2PKHAddress::from_pubkey_hash(&buf) {
-                    if let Ok(v) = v.to_string() {
-                        address = Some(v)
-                    }
-                }
-            }
+            // Only the script types that have addresses yield one
+            let template = ScriptTemplate::detect(&script_bits);
+            limb.script_type = Some(template.script_type);
 
-            let bits: Vec<ScriptBit> = script
-                .into_iter()
-                .flat_map(|e| flatten_script_bits(&[e]))
-                .collect();
+            let bits: Vec<ScriptBit> = match parse_config.nested {
+                true => script_bits,
+                false => flatten_script_bits(&script_bits),
+            };
 
-            for (i, chunk) in bits.iter().enumerate() {
-                counter.chunk_index = i;
+            let settings: Vec<SplitConfig> = parse_config
+                .split
+                .iter()
+                .filter(|setting| setting.applies_to(false, i))
+                .cloned()
+                .collect();
 
+            for chunk in bits.iter() {
                 Self::extract_cells(
                     &mut limb,
                     &mut cell,
                     &mut counter,
                     chunk,
                     &settings,
-                    parse_config.transform,
-                );
+                    parse_config,
+                )?;
             }
             if !cell.is_empty() {
                 limb.tape.push(Tape {
                     cell,
                     i: counter.tape_index,
+                    protocol: None,
                 });
             }
 
-            let sender = SendRecv {
+            limb.chunks = Self::trailing_chunks(&limb, &counter);
+
+            if let Some(registry) = &parse_config.bitcom {
+                registry.label(&mut limb.tape);
+            }
+
+            let mut sender = SendRecv {
                 h: None,
                 i: i as u32,
-                a: Some(address.unwrap_or("false".to_string())),
                 v: Some(output.get_satoshis()),
+                ..Default::default()
             };
+            sender.set_address(template.key(), parse_config);
 
             limb.e = Some(sender);
 
             results.r#out.push(limb);
         }
 
+        if parse_config.stats {
+            let input_values: Vec<Option<u64>> = results
+                .r#in
+                .iter()
+                .map(|input| input.e.as_ref().and_then(|e| e.v))
+                .collect();
+            let output_values: Vec<u64> = outputs.iter().map(TxOut::get_satoshis).collect();
+
+            results.stats = Some(TxStats::new(
+                tx.to_bytes()?.len(),
+                &input_values,
+                &output_values,
+            ));
+        }
+
         Ok(results)
     }
 
-    /// Recursively maps each script chunk (Cell) to the Input/Output Tape
+    /// Recursively maps each script chunk (Cell) to the Input/Output Tape.
+    /// Conditionals are kept as child Tapes, so chunks are flattened beforehand unless `nested`.
     pub fn extract_cells(
         xput: &mut IO,
         cell: &mut Vec<Cell>,
         counter: &mut IndexCounter,
         chunk: &ScriptBit,
         settings: &[SplitConfig],
-        _transform: Option<fn(Cell, &ScriptBit) -> Cell>,
-    ) {
-        let mut is_splitter = false;
-        let mut splitter: Option<Include> = None;
-
+        parse_config: &ParseConfig,
+    ) -> Result<()> {
         match chunk {
             ScriptBit::OpCode(op_code) => {
-                let op = Some(*op_code as u8);
-                let ops = Some(op_code.to_string());
+                let op = *op_code as u8;
+                let ops = op_code.to_string();
 
-                for setting in settings {
-                    if setting.token.op == op || setting.token.ops == ops {
-                        splitter = setting.include.to_owned();
-                        is_splitter = true;
-                    }
-                }
+                let splitter = settings.iter().rev().find(|setting| {
+                    setting.is_active(counter.op_return) && setting.token.matches_op(op, &ops)
+                });
 
-                if is_splitter {
-                    let splitter = splitter.unwrap();
-
-                    match splitter {
-                        Include::Left => {
-                            let item: Cell = maybe_transform(
-                                Cell {
-                                    op,
-                                    ops,
-                                    ii: counter.chunk_index,
-                                    i: counter.cell_index,
-                                    ..Default::default()
-                                },
-                                chunk,
-                                _transform,
-                            );
-                            counter.cell_index += 1;
-
-                            cell.push(item);
-
-                            xput.tape.push(Tape {
-                                cell: cell.to_owned(),
-                                i: counter.tape_index,
-                            });
-
-                            counter.tape_index += 1;
-
-                            cell.clear();
-                            counter.cell_index = 0;
-                        }
-                        Include::Right => {
-                            xput.tape.push(Tape {
-                                cell: cell.to_vec(),
-                                i: counter.tape_index,
-                            });
-                            counter.tape_index += 1;
-
-                            let item: Cell = maybe_transform(
-                                Cell {
-                                    op,
-                                    ops,
-                                    ii: counter.chunk_index,
-                                    i: counter.cell_index,
-                                    ..Default::default()
-                                },
-                                chunk,
-                                _transform,
-                            );
-
-                            *cell = vec![item];
-                            counter.cell_index = 1;
-                        }
-                        Include::Center => {
-                            xput.tape.push(Tape {
-                                cell: cell.to_vec(),
-                                i: counter.tape_index,
-                            });
-                            counter.tape_index += 1;
-
-                            let item: Cell = maybe_transform(
-                                Cell {
-                                    op,
-                                    ops,
-                                    ii: counter.chunk_index,
-                                    i: 0,
-                                    ..Default::default()
-                                },
-                                chunk,
-                                _transform,
-                            );
-
-                            xput.tape.push(Tape {
-                                cell: vec![item],
-                                i: counter.tape_index,
-                            });
-
-                            cell.clear();
-                            counter.cell_index = 0;
-                        }
-                    }
-                } else {
-                    let item: Cell = maybe_transform(
-                        Cell {
-                            op,
-                            ops,
-                            ii: counter.chunk_index,
-                            i: counter.cell_index,
-                            ..Default::default()
-                        },
-                        chunk,
-                        _transform,
-                    );
-
-                    cell.push(item);
-                    counter.cell_index += 1;
+                let item = Cell {
+                    op: Some(op),
+                    ops: Some(ops),
+                    n: parse_config.numbers.then(|| decode_small_int(op)).flatten(),
+                    ..Default::default()
+                };
+
+                Self::push_cell(xput, cell, counter, chunk, item, splitter, parse_config);
+
+                if *op_code == OpCodes::OP_RETURN {
+                    counter.op_return = true;
                 }
+
+                counter.chunk_index += 1;
             }
             ScriptBit::If { code, pass, fail } => {
-                Self::extract_cells(
-                    xput,
-                    cell,
+                // Branches become child Tapes of the OP_IF Cell, while OP_ELSE and OP_ENDIF
+                // keep their position (`ii`) in the script as regular Cells
+                let if_index = counter.chunk_index;
+
+                let (pass, else_index) = Self::extract_branch(
+                    xput.i,
+                    pass,
+                    if_index + 1,
                     counter,
-                    &ScriptBit::OpCode(code.to_owned()),
                     settings,
-                    _transform,
-                );
+                    parse_config,
+                )?;
+
+                let (fail, endif_index) = match fail {
+                    Some(fail) => {
+                        let (fail, endif_index) = Self::extract_branch(
+                            xput.i,
+                            fail,
+                            else_index + 1,
+                            counter,
+                            settings,
+                            parse_config,
+                        )?;
+                        (Some(fail), endif_index)
+                    }
+                    None => (None, else_index),
+                };
 
-                for bit in pass {
-                    Self::extract_cells(xput, cell, counter, bit, settings, _transform)
-                }
+                let op = *code as u8;
+                let ops = code.to_string();
 
-                if let Some(fail_script) = fail {
-                    for bit in fail_script {
-                        Self::extract_cells(xput, cell, counter, bit, settings, _transform)
-                    }
+                let splitter = settings.iter().rev().find(|setting| {
+                    setting.is_active(counter.op_return) && setting.token.matches_op(op, &ops)
+                });
+
+                let has_else = fail.is_some();
+
+                let item = Cell {
+                    op: Some(op),
+                    ops: Some(ops),
+                    pass: Some(pass),
+                    fail,
+                    ..Default::default()
+                };
+
+                Self::push_cell(xput, cell, counter, chunk, item, splitter, parse_config);
+
+                if has_else {
+                    counter.chunk_index = else_index;
+                    Self::extract_cells(
+                        xput,
+                        cell,
+                        counter,
+                        &ScriptBit::OpCode(OpCodes::OP_ELSE),
+                        settings,
+                        parse_config,
+                    )?;
                 }
+
+                counter.chunk_index = endif_index;
+                Self::extract_cells(
+                    xput,
+                    cell,
+                    counter,
+                    &ScriptBit::OpCode(OpCodes::OP_ENDIF),
+                    settings,
+                    parse_config,
+                )?;
             }
             _ => {
                 if let Some(bytes) = chunk.inner() {
                     let string = String::from_utf8_lossy(&bytes).to_string();
 
-                    for setting in settings {
-                        if setting.token.b.as_ref() == Some(&bytes)
-                            || setting.token.s.as_ref() == Some(&string)
-                        {
-                            splitter = setting.include.to_owned();
-                            is_splitter = true;
-                        }
-                    }
+                    let splitter = settings.iter().rev().find(|setting| {
+                        setting.is_active(counter.op_return)
+                            && setting.token.matches_push(&bytes, &string)
+                    });
 
-                    if is_splitter {
-                        let splitter = splitter.unwrap();
-                        match splitter {
-                            Include::Left => {
-                                let item: Cell = maybe_transform(
-                                    Cell {
-                                        b: Some(bytes),
-                                        s: Some(string),
-                                        ii: counter.chunk_index,
-                                        i: counter.cell_index,
-                                        ..Default::default()
-                                    },
-                                    chunk,
-                                    _transform,
-                                );
-
-                                cell.push(item);
-                                counter.cell_index += 1;
-
-                                xput.tape.push(Tape {
-                                    cell: cell.to_vec(),
-                                    i: counter.tape_index,
-                                });
-
-                                counter.tape_index += 1;
-
-                                cell.clear();
-                                counter.cell_index = 0;
-                            }
-                            Include::Right => {
-                                xput.tape.push(Tape {
-                                    cell: cell.to_vec(),
-                                    i: counter.tape_index,
-                                });
-                                counter.tape_index += 1;
-
-                                let item: Cell = maybe_transform(
-                                    Cell {
-                                        b: Some(bytes),
-                                        s: Some(string),
-                                        ii: counter.chunk_index,
-                                        i: counter.cell_index,
-                                        ..Default::default()
-                                    },
-                                    chunk,
-                                    _transform,
-                                );
-
-                                *cell = vec![item];
-                                counter.cell_index = 1;
-                            }
-                            Include::Center => {
-                                xput.tape.push(Tape {
-                                    cell: cell.to_vec(),
-                                    i: counter.tape_index,
-                                });
-                                counter.tape_index += 1;
-
-                                let item: Cell = maybe_transform(
-                                    Cell {
-                                        b: Some(bytes),
-                                        s: Some(string),
-                                        ii: counter.chunk_index,
-                                        i: 0,
-                                        ..Default::default()
-                                    },
-                                    chunk,
-                                    _transform,
-                                );
-
-                                xput.tape.push(Tape {
-                                    cell: vec![item],
-                                    i: counter.tape_index,
-                                });
-
-                                cell.clear();
-                                counter.cell_index = 0;
-                            }
-                        }
-                    } else {
-                        let item: Cell = maybe_transform(
-                            Cell {
-                                b: Some(bytes.to_vec()),
-                                s: Some(string.to_string()),
-                                ii: counter.chunk_index,
-                                i: counter.cell_index,
-                                ..Default::default()
-                            },
-                            chunk,
-                            _transform,
-                        );
-                        cell.push(item);
-                        counter.cell_index += 1;
-                    }
+                    let push = match chunk {
+                        ScriptBit::Push(data) => Some(data.len() as u8),
+                        ScriptBit::PushData(code, _) => Some(*code as u8),
+                        _ => None,
+                    };
+
+                    let item = parse_config.data_cell(bytes, string, push)?;
+
+                    Self::push_cell(xput, cell, counter, chunk, item, splitter, parse_config);
+                }
+
+                counter.chunk_index += 1;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Chunk count of a script whose last chunks were discarded by a split (`None` otherwise)
+    fn trailing_chunks(xput: &IO, counter: &IndexCounter) -> Option<usize> {
+        let mut cells: Vec<&Cell> = vec![];
+        collect_cells(&xput.tape, &mut cells);
+
+        let end = cells.iter().map(|cell| cell.ii + 1).max().unwrap_or(0);
+        (counter.chunk_index > end).then_some(counter.chunk_index)
+    }
+
+    /// Maps a conditional branch to its own Tapes, returning them with the next chunk index
+    fn extract_branch(
+        outer_index: usize,
+        bits: &[ScriptBit],
+        chunk_index: usize,
+        counter: &IndexCounter,
+        settings: &[SplitConfig],
+        parse_config: &ParseConfig,
+    ) -> Result<(Vec<Tape>, usize)> {
+        let mut branch = IO::new(outer_index);
+        let mut cell: Vec<Cell> = vec![];
+        let mut branch_counter = IndexCounter {
+            chunk_index,
+            op_return: counter.op_return,
+            ..IndexCounter::new(outer_index)
+        };
+
+        for bit in bits {
+            Self::extract_cells(
+                &mut branch,
+                &mut cell,
+                &mut branch_counter,
+                bit,
+                settings,
+                parse_config,
+            )?;
+        }
+
+        if !cell.is_empty() {
+            branch.tape.push(Tape {
+                cell,
+                i: branch_counter.tape_index,
+                protocol: None,
+            });
+        }
+
+        Ok((branch.tape, branch_counter.chunk_index))
+    }
+
+    /// Indexes and transforms a Cell, then places it according to the matching splitter (if any)
+    fn push_cell(
+        xput: &mut IO,
+        cell: &mut Vec<Cell>,
+        counter: &mut IndexCounter,
+        chunk: &ScriptBit,
+        item: Cell,
+        splitter: Option<&SplitConfig>,
+        parse_config: &ParseConfig,
+    ) {
+        let transform = parse_config.transform.as_deref();
+
+        let Some(splitter) = splitter else {
+            let item = maybe_transform(
+                Cell {
+                    ii: counter.chunk_index,
+                    i: counter.cell_index,
+                    ..item
+                },
+                chunk,
+                transform,
+            );
+
+            cell.push(item);
+            counter.cell_index += 1;
+            return;
+        };
+
+        match splitter.include {
+            Some(Include::Left) => {
+                let item = maybe_transform(
+                    Cell {
+                        ii: counter.chunk_index,
+                        i: counter.cell_index,
+                        ..item
+                    },
+                    chunk,
+                    transform,
+                );
+
+                cell.push(item);
+
+                xput.tape.push(Tape {
+                    cell: cell.to_vec(),
+                    i: counter.tape_index,
+                    protocol: None,
+                });
+                counter.tape_index += 1;
+
+                cell.clear();
+                counter.cell_index = 0;
+            }
+            Some(Include::Right) => {
+                xput.tape.push(Tape {
+                    cell: cell.to_vec(),
+                    i: counter.tape_index,
+                    protocol: None,
+                });
+                counter.tape_index += 1;
+
+                let item = maybe_transform(
+                    Cell {
+                        ii: counter.chunk_index,
+                        i: 0,
+                        ..item
+                    },
+                    chunk,
+                    transform,
+                );
+
+                *cell = vec![item];
+                counter.cell_index = 1;
+            }
+            Some(Include::Center) => {
+                xput.tape.push(Tape {
+                    cell: cell.to_vec(),
+                    i: counter.tape_index,
+                    protocol: None,
+                });
+                counter.tape_index += 1;
+
+                let item = maybe_transform(
+                    Cell {
+                        ii: counter.chunk_index,
+                        i: 0,
+                        ..item
+                    },
+                    chunk,
+                    transform,
+                );
+
+                xput.tape.push(Tape {
+                    cell: vec![item],
+                    i: counter.tape_index,
+                    protocol: None,
+                });
+                counter.tape_index += 1;
+
+                cell.clear();
+                counter.cell_index = 0;
+            }
+            None => {
+                if !cell.is_empty() {
+                    xput.tape.push(Tape {
+                        cell: cell.to_vec(),
+                        i: counter.tape_index,
+                        protocol: None,
+                    });
+                    counter.tape_index += 1;
                 }
+
+                cell.clear();
+                counter.cell_index = 0;
             }
         }
     }
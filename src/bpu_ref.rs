@@ -0,0 +1,503 @@
+use crate::components::*;
+use crate::script_type::{AddressKey, ScriptChunk, ScriptTemplate, ScriptType};
+use base64::Engine;
+use bsv::{Hash, OpCodes, ScriptBit};
+use num_traits::FromPrimitive;
+use serde::ser::SerializeStruct;
+use serde::{Serialize, Serializer};
+use std::borrow::Cow;
+
+const OP_RETURN: u8 = 0x6a;
+const OP_PUSHDATA1: u8 = 0x4c;
+const OP_PUSHDATA2: u8 = 0x4d;
+const OP_PUSHDATA4: u8 = 0x4e;
+
+/// Borrowed Bitcoin Processing Unit
+///
+/// Cells point into the raw transaction buffer and are only encoded when serialized,
+/// producing the same JSON as `BPU` with the default encoding. Split rules, `push_info`,
+/// `network`, `address_mode` and `prev_outs` are honored, while transforms, encodings,
+/// large pushes, blob offloading, numbers, nested Tapes, stats and Bitcom labels only
+/// apply to the owned `BPU`.
+#[derive(Debug)]
+pub struct BpuRef<'a> {
+    pub raw: &'a [u8],
+    pub version: u32,
+    pub r#out: Vec<IORef<'a>>,
+    pub r#in: Vec<IORef<'a>>,
+    pub lock: u32,
+}
+
+#[derive(Debug, Serialize)]
+/// Borrowed Transaction Input/Output Object
+pub struct IORef<'a> {
+    pub i: usize,
+    pub tape: Vec<TapeRef<'a>>,
+    pub e: SendRecvRef<'a>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seq: Option<u32>,
+    /// Locking script type (outputs only)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub script_type: Option<ScriptType>,
+    /// Chunk count of the script, when its last chunks were discarded by a split
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub chunks: Option<usize>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TapeRef<'a> {
+    pub cell: Vec<CellRef<'a>>,
+    pub i: usize,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct CellRef<'a> {
+    /// Opcode (`None` for data pushes)
+    pub op: Option<u8>,
+    /// Push data (`None` for opcodes)
+    pub b: Option<&'a [u8]>,
+    /// Opcode used to push the data (`push_info` mode only)
+    pub push: Option<u8>,
+    pub ii: usize,
+    pub i: usize,
+}
+
+impl<'a> CellRef<'a> {
+    /// Push data as a (lossy) UTF-8 string, borrowed when the push is valid UTF-8
+    pub fn s(&self) -> Option<Cow<'a, str>> {
+        self.b.map(String::from_utf8_lossy)
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct SendRecvRef<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub h: Option<TxidRef<'a>>,
+    pub i: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub v: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub a: Option<Address>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hash160: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pubkey: Option<String>,
+}
+
+impl SendRecvRef<'_> {
+    /// Sets the address (following `address_mode`) and the key material it derives from
+    pub fn set_address(&mut self, key: Option<AddressKey>, parse_config: &ParseConfig) {
+        (self.a, self.hash160, self.pubkey) = address_fields(key, parse_config);
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+/// A txid in internal (little-endian) byte order, serialized as display hex
+pub struct TxidRef<'a>(pub &'a [u8]);
+
+impl TxidRef<'_> {
+    pub fn to_hex(&self) -> String {
+        let mut bytes = self.0.to_vec();
+        bytes.reverse();
+        hex::encode(bytes)
+    }
+}
+
+impl Serialize for TxidRef<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_hex())
+    }
+}
+
+impl Serialize for CellRef<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("Cell", 4)?;
+
+        if let Some(op) = self.op {
+            state.serialize_field("op", &op)?;
+            if let Some(ops) = op_name(op) {
+                state.serialize_field("ops", &ops)?;
+            }
+        }
+
+        if let Some(bytes) = self.b {
+            state.serialize_field(
+                "b",
+                &base64::engine::general_purpose::STANDARD.encode(bytes),
+            )?;
+            state.serialize_field("s", &String::from_utf8_lossy(bytes))?;
+            if let Some(push) = self.push {
+                state.serialize_field("push", &push)?;
+                state.serialize_field("len", &bytes.len())?;
+            }
+        }
+
+        state.serialize_field("ii", &self.ii)?;
+        state.serialize_field("i", &self.i)?;
+        state.end()
+    }
+}
+
+impl Serialize for BpuRef<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("BPU", 4)?;
+        state.serialize_field("out", &self.r#out)?;
+        state.serialize_field("in", &self.r#in)?;
+        state.serialize_field(
+            "tx",
+            &Tx {
+                h: Some(self.txid()),
+                v: Some(self.version),
+                ..Default::default()
+            },
+        )?;
+        state.serialize_field("lock", &self.lock)?;
+        state.end()
+    }
+}
+
+impl std::fmt::Display for BpuRef<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", serde_json::to_string(self).unwrap())
+    }
+}
+
+/// Returns the opcode name, e.g. `OP_RETURN`
+fn op_name(op: u8) -> Option<String> {
+    OpCodes::from_u8(op).map(|op_code| op_code.to_string())
+}
+
+impl<'a> BpuRef<'a> {
+    /// Parses a serialized transaction without copying its scripts
+    pub fn parse(raw: &'a [u8], parse_config: &ParseConfig) -> Result<BpuRef<'a>> {
+        let mut cursor = Cursor::new(raw);
+
+        let version = cursor.u32()?;
+
+        let mut results = BpuRef {
+            raw,
+            version,
+            r#in: vec![],
+            r#out: vec![],
+            lock: 0,
+        };
+
+        let n_inputs = cursor.varint()?;
+        for i in 0..n_inputs as usize {
+            let prev_tx_id = cursor.take(32)?;
+            let vout = cursor.u32()?;
+            let script_len = cursor.varint()? as usize;
+            let script = cursor.take(script_len)?;
+            let seq = cursor.u32()?;
+
+            let prev_tx_id = TxidRef(prev_tx_id);
+            let prev_out = match &parse_config.prev_outs {
+                Some(provider) => provider.prev_out(&prev_tx_id.to_hex(), vout)?,
+                None => None,
+            };
+
+            // The spent output gives the exact address, otherwise guess from the unlocking script
+            let key = match &prev_out {
+                Some(prev_out) => {
+                    let prev_bits: Vec<ScriptBit> = prev_out.script.clone().into_iter().collect();
+                    ScriptTemplate::detect(&prev_bits).key()
+                }
+                None => Self::unlocking_key(script),
+            };
+
+            let mut sender = SendRecvRef {
+                h: Some(prev_tx_id),
+                i: vout,
+                v: prev_out.map(|prev_out| prev_out.satoshis),
+                a: None,
+                hash160: None,
+                pubkey: None,
+            };
+            sender.set_address(key, parse_config);
+
+            let settings: Vec<&SplitConfig> = parse_config
+                .split
+                .iter()
+                .filter(|setting| setting.applies_to(true, i))
+                .collect();
+
+            let (tape, chunks) = Self::extract_tapes(i, script, &settings, parse_config)?;
+
+            results.r#in.push(IORef {
+                i,
+                tape,
+                e: sender,
+                seq: Some(seq),
+                script_type: None,
+                chunks,
+            });
+        }
+
+        let n_outputs = cursor.varint()?;
+        for i in 0..n_outputs as usize {
+            let satoshis = cursor.u64()?;
+            let script_len = cursor.varint()? as usize;
+            let script = cursor.take(script_len)?;
+
+            // Only the script types that have addresses yield one
+            let template = ScriptTemplate::detect_chunks(&Self::chunks(script)?);
+
+            let mut sender = SendRecvRef {
+                h: None,
+                i: i as u32,
+                v: Some(satoshis),
+                a: None,
+                hash160: None,
+                pubkey: None,
+            };
+            sender.set_address(template.key(), parse_config);
+
+            let settings: Vec<&SplitConfig> = parse_config
+                .split
+                .iter()
+                .filter(|setting| setting.applies_to(false, i))
+                .collect();
+
+            let (tape, chunks) = Self::extract_tapes(i, script, &settings, parse_config)?;
+
+            results.r#out.push(IORef {
+                i,
+                tape,
+                e: sender,
+                seq: None,
+                script_type: Some(template.script_type),
+                chunks,
+            });
+        }
+
+        results.lock = cursor.u32()?;
+
+        if !cursor.is_empty() {
+            return Err(BpuError::CustomError(
+                "Unexpected trailing bytes after transaction".to_string(),
+            ));
+        }
+
+        Ok(results)
+    }
+
+    /// Transaction id (display hex)
+    pub fn txid(&self) -> String {
+        let hash = Hash::sha_256d(self.raw).to_bytes();
+        TxidRef(&hash).to_hex()
+    }
+
+    /// Chunks of a raw script, borrowing their push data
+    fn chunks(script: &'a [u8]) -> Result<Vec<ScriptChunk<'a>>> {
+        let mut chunks = vec![];
+
+        let mut cursor = Cursor::new(script);
+        while !cursor.is_empty() {
+            chunks.push(cursor.chunk()?);
+        }
+
+        Ok(chunks)
+    }
+
+    /// Key guessed from the second push of an unlocking script, as `BPU::collect` does
+    fn unlocking_key(script: &[u8]) -> Option<AddressKey> {
+        let mut cursor = Cursor::new(script);
+        cursor.chunk().ok()?;
+
+        match cursor.chunk().ok()? {
+            // Public Key
+            (0x01..=0x4b, Some(buf)) if buf.len() == 33 && (buf[0] == 2 || buf[0] == 3) => {
+                AddressKey::from_pubkey(buf)
+            }
+            // P2PKH
+            (0x01..=0x4b, Some(buf)) if buf.len() == 20 => AddressKey::from_hash160(buf),
+            _ => None,
+        }
+    }
+
+    /// Splits a raw script into Tapes, following the same rules as `BPU::extract_cells`.
+    /// Also returns the chunk count when the last chunks were discarded (see `IO::chunks`).
+    fn extract_tapes(
+        outer_index: usize,
+        script: &'a [u8],
+        settings: &[&SplitConfig],
+        parse_config: &ParseConfig,
+    ) -> Result<(Vec<TapeRef<'a>>, Option<usize>)> {
+        let mut tape: Vec<TapeRef<'a>> = vec![];
+        let mut cell: Vec<CellRef<'a>> = vec![];
+        let mut counter = IndexCounter::new(outer_index);
+
+        let mut cursor = Cursor::new(script);
+        while !cursor.is_empty() {
+            let (op, bytes) = cursor.chunk()?;
+
+            let splitter = settings.iter().rev().find(|setting| {
+                setting.is_active(counter.op_return)
+                    && match bytes {
+                        Some(bytes) => setting
+                            .token
+                            .matches_push(bytes, &String::from_utf8_lossy(bytes)),
+                        None => {
+                            setting.token.op == Some(op)
+                                || (setting.token.ops.is_some() && setting.token.ops == op_name(op))
+                        }
+                    }
+            });
+
+            let item = CellRef {
+                op: bytes.is_none().then_some(op),
+                b: bytes,
+                push: (bytes.is_some() && parse_config.push_info).then_some(op),
+                ii: counter.chunk_index,
+                i: counter.cell_index,
+            };
+
+            match splitter.map(|setting| &setting.include) {
+                None => {
+                    cell.push(item);
+                    counter.cell_index += 1;
+                }
+                Some(Some(Include::Left)) => {
+                    cell.push(item);
+                    tape.push(TapeRef {
+                        cell: std::mem::take(&mut cell),
+                        i: counter.tape_index,
+                    });
+                    counter.tape_index += 1;
+                    counter.cell_index = 0;
+                }
+                Some(Some(Include::Right)) => {
+                    tape.push(TapeRef {
+                        cell: std::mem::take(&mut cell),
+                        i: counter.tape_index,
+                    });
+                    counter.tape_index += 1;
+
+                    cell.push(CellRef { i: 0, ..item });
+                    counter.cell_index = 1;
+                }
+                Some(Some(Include::Center)) => {
+                    tape.push(TapeRef {
+                        cell: std::mem::take(&mut cell),
+                        i: counter.tape_index,
+                    });
+                    counter.tape_index += 1;
+
+                    tape.push(TapeRef {
+                        cell: vec![CellRef { i: 0, ..item }],
+                        i: counter.tape_index,
+                    });
+                    counter.tape_index += 1;
+                    counter.cell_index = 0;
+                }
+                Some(None) => {
+                    if !cell.is_empty() {
+                        tape.push(TapeRef {
+                            cell: std::mem::take(&mut cell),
+                            i: counter.tape_index,
+                        });
+                        counter.tape_index += 1;
+                    }
+                    counter.cell_index = 0;
+                }
+            }
+
+            if op == OP_RETURN && bytes.is_none() {
+                counter.op_return = true;
+            }
+            counter.chunk_index += 1;
+        }
+
+        if !cell.is_empty() {
+            tape.push(TapeRef {
+                cell,
+                i: counter.tape_index,
+            });
+        }
+
+        let end = tape
+            .iter()
+            .flat_map(|tape| &tape.cell)
+            .map(|cell| cell.ii + 1)
+            .max()
+            .unwrap_or(0);
+        let chunks = (counter.chunk_index > end).then_some(counter.chunk_index);
+
+        Ok((tape, chunks))
+    }
+}
+
+/// Reads little-endian values and script chunks from a borrowed buffer
+pub(crate) struct Cursor<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Cursor<'a> {
+    pub(crate) fn new(buf: &'a [u8]) -> Self {
+        Self { buf, pos: 0 }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.pos >= self.buf.len()
+    }
+
+    pub(crate) fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|end| *end <= self.buf.len());
+
+        match end {
+            Some(end) => {
+                let bytes = &self.buf[self.pos..end];
+                self.pos = end;
+                Ok(bytes)
+            }
+            None => Err(BpuError::CustomError(format!(
+                "Unexpected end of buffer reading {} bytes at offset {}",
+                len, self.pos
+            ))),
+        }
+    }
+
+    pub(crate) fn u8(&mut self) -> Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    pub(crate) fn u16(&mut self) -> Result<u16> {
+        Ok(u16::from_le_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    pub(crate) fn u32(&mut self) -> Result<u32> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    pub(crate) fn u64(&mut self) -> Result<u64> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    pub(crate) fn varint(&mut self) -> Result<u64> {
+        Ok(match self.u8()? {
+            0xfd => self.u16()? as u64,
+            0xfe => self.u32()? as u64,
+            0xff => self.u64()?,
+            n => n as u64,
+        })
+    }
+
+    /// Reads one script chunk, returning its opcode and push data (if any)
+    pub(crate) fn chunk(&mut self) -> Result<(u8, Option<&'a [u8]>)> {
+        let op = self.u8()?;
+
+        let len = match op {
+            0x01..=0x4b => op as usize,
+            OP_PUSHDATA1 => self.u8()? as usize,
+            OP_PUSHDATA2 => self.u16()? as usize,
+            OP_PUSHDATA4 => self.u32()? as usize,
+            _ => return Ok((op, None)),
+        };
+
+        Ok((op, Some(self.take(len)?)))
+    }
+}
@@ -1,8 +1,15 @@
 use std::collections::HashMap;
+use std::sync::Arc;
 
+use crate::{
+    AddressKey, BitcomRegistry, BlobConfig, BlockHeader, Network, PrevOutProvider, ScriptType, BPU,
+};
+
+use base64::Engine;
+use regex::Regex;
 use serde::{Deserialize, Serialize};
 use serde_with::base64::Base64;
-use serde_with::{serde_as, skip_serializing_none};
+use serde_with::{serde_as, skip_serializing_none, DisplayFromStr};
 
 use thiserror::Error;
 
@@ -26,21 +33,74 @@ pub type Result<T> = std::result::Result<T, BpuError>;
 pub struct Tx {
     pub h: Option<String>, // txid
     pub r: Option<String>, // raw hex
+    pub v: Option<u32>,    // version
+}
+
+#[skip_serializing_none]
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+/// Transaction summary. Input totals, fee and fee rate need every input value
+/// (see `ParseConfig::prev_outs`)
+pub struct TxStats {
+    pub size: usize,           // serialized bytes
+    pub input: Option<u64>,    // total input satoshis
+    pub output: Option<u64>,   // total output satoshis (None on overflow)
+    pub fee: Option<u64>,      // satoshis
+    pub fee_rate: Option<f64>, // sat/byte
+}
+
+impl TxStats {
+    pub fn new(size: usize, input_values: &[Option<u64>], output_values: &[u64]) -> Self {
+        let input = input_values
+            .iter()
+            .try_fold(0u64, |total, value| total.checked_add((*value)?));
+        let output = output_values
+            .iter()
+            .try_fold(0u64, |total, value| total.checked_add(*value));
+        let fee = input
+            .zip(output)
+            .and_then(|(input, output)| input.checked_sub(output));
+
+        TxStats {
+            size,
+            input,
+            output,
+            fee,
+            fee_rate: fee.filter(|_| size > 0).map(|fee| fee as f64 / size as f64),
+        }
+    }
 }
 
 #[serde_as]
+#[skip_serializing_none]
 #[derive(Debug, Serialize, Default)]
 /// Extends BPU
 pub struct BobTx {
     /// Block Info (index, hash, timestamp)
     pub blk: Option<Block>,
-    pub mem: u64,
+    /// Mempool time (unix seconds), for unconfirmed transactions
+    pub mem: Option<u64>,
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
     pub fields: HashMap<String, String>,
     pub r#out: Vec<IO>,
     pub r#in: Vec<IO>,
     pub tx: Tx,
     pub lock: Option<u32>,
 }
+
+impl BobTx {
+    pub fn new(bpu: BPU, blk: Option<Block>, mem: Option<u64>) -> Self {
+        Self {
+            blk,
+            mem,
+            fields: Default::default(),
+            r#out: bpu.r#out,
+            r#in: bpu.r#in,
+            tx: bpu.tx,
+            lock: bpu.lock,
+        }
+    }
+}
+
 #[serde_as]
 #[skip_serializing_none]
 #[derive(Debug, Serialize, Clone, Default)]
@@ -48,6 +108,20 @@ pub struct OrdData {
     #[serde_as(as = "Base64")]
     pub data: Vec<u8>, // base64
     pub content_type: String,
+    /// Body pushes in order, when any was offloaded to a BlobStore (`data` is then empty)
+    pub chunks: Option<Vec<OrdChunk>>,
+}
+
+#[serde_as]
+#[derive(Debug, Serialize, Clone)]
+/// One push of an inscription body
+pub enum OrdChunk {
+    /// Inline bytes (base64)
+    #[serde(rename = "b")]
+    Data(#[serde_as(as = "Base64")] Vec<u8>),
+    /// SHA-256 hash (hex) of bytes offloaded to a BlobStore
+    #[serde(rename = "f")]
+    Blob(String),
 }
 
 #[serde_as]
@@ -75,26 +149,84 @@ pub struct BMapManager {
 
 #[serde_as]
 #[skip_serializing_none]
-#[derive(Debug, Serialize, Default)]
+#[derive(Debug, Serialize, Clone, Default)]
 pub struct Block {
     i: u32,            // index
     t: u32,            // time
     h: Option<String>, // hash
+    p: Option<u32>,    // position of the transaction in the block
+    #[serde(skip)]
+    header: Option<BlockHeader>,
 }
 
-#[derive(Debug, Serialize, Clone)]
+impl Block {
+    pub fn new(height: u32, time: u32, hash: Option<String>) -> Self {
+        Self {
+            i: height,
+            t: time,
+            h: hash,
+            p: None,
+            header: None,
+        }
+    }
+
+    /// Builds Block Info from a serialized 80-byte block header
+    pub fn from_header(header: &[u8], height: u32) -> Result<Self> {
+        Ok(BlockHeader::from_bytes(header)?.block(height))
+    }
+
+    pub fn with_header(mut self, header: BlockHeader) -> Self {
+        self.header = Some(header);
+        self
+    }
+
+    pub fn with_position(mut self, position: u32) -> Self {
+        self.p = Some(position);
+        self
+    }
+
+    pub fn height(&self) -> u32 {
+        self.i
+    }
+
+    pub fn time(&self) -> u32 {
+        self.t
+    }
+
+    pub fn hash(&self) -> Option<&str> {
+        self.h.as_deref()
+    }
+
+    /// Position of the transaction in the block, when parsed from one
+    pub fn position(&self) -> Option<u32> {
+        self.p
+    }
+
+    /// Header fields (prev hash, merkle root, bits, nonce), when built from a header
+    pub fn header(&self) -> Option<&BlockHeader> {
+        self.header.as_ref()
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
 /// - With `Include::Left`, you can merge the delimiter to the left side of the split arrays
 /// - With `Include::Right`, you can merge the delimiter to the left side of the split arrays
 /// - With `Include::Center`,  you can create a new standalone cell which contains the delimiter
+///
+/// Serialized as `"l"`, `"r"` and `"c"` to match the JS BPU config format.
 pub enum Include {
+    #[serde(rename = "l")]
     Left,
+    #[serde(rename = "r")]
     Right,
+    #[serde(rename = "c")]
     Center,
 }
 
 #[serde_as]
 #[skip_serializing_none]
-#[derive(Debug, Serialize, Clone, Default)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
 /// The Cell we want to split (w/o index)
 pub struct Token {
     pub op: Option<u8>,
@@ -102,30 +234,282 @@ pub struct Token {
     #[serde_as(as = "Option<Base64>")]
     pub b: Option<Vec<u8>>,
     pub s: Option<String>,
+    /// Matches pushes whose UTF-8 string starts with this prefix
+    pub sp: Option<String>,
+    /// Matches pushes whose UTF-8 string matches this regex
+    #[serde_as(as = "Option<DisplayFromStr>")]
+    pub re: Option<Regex>,
+    /// Matches pushes whose bytes start with this prefix
+    #[serde_as(as = "Option<Base64>")]
+    pub bp: Option<Vec<u8>>,
+    /// Matches pushes of exactly this many bytes
+    pub len: Option<usize>,
 }
 
-#[derive(Debug, Serialize, Clone)]
+impl Token {
+    pub fn matches_op(&self, op: u8, ops: &str) -> bool {
+        self.op == Some(op) || self.ops.as_deref() == Some(ops)
+    }
+
+    pub fn matches_push(&self, bytes: &[u8], string: &str) -> bool {
+        self.b.as_deref() == Some(bytes)
+            || self.s.as_deref() == Some(string)
+            || self.sp.as_deref().is_some_and(|sp| string.starts_with(sp))
+            || self.re.as_ref().is_some_and(|re| re.is_match(string))
+            || self.bp.as_ref().is_some_and(|bp| bytes.starts_with(bp))
+            || self.len == Some(bytes.len())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct SplitConfig {
     /// - With `Include::Left`, you can merge the delimiter to the left side of the split arrays
     /// - With `Include::Right`, you can merge the delimiter to the left side of the split arrays
     /// - With `Include::Center`,  you can create a new standalone cell which contains the delimiter
+    /// - With `None`, the delimiter is discarded
     pub include: Option<Include>,
     /// The tokens we wish to split off from the main sequence of ScriptBits
     pub token: Token,
+    /// Where the split applies (both inputs and outputs when `None`)
+    pub scope: Option<Scope>,
+}
+
+impl SplitConfig {
+    /// Whether the split applies to the input (`is_input`) or output at `index`
+    pub fn applies_to(&self, is_input: bool, index: usize) -> bool {
+        match &self.scope {
+            Some(Scope::Inputs) => is_input,
+            Some(Scope::Outputs) => !is_input,
+            Some(Scope::OutputIndices(indices)) => !is_input && indices.contains(&index),
+            Some(Scope::AfterOpReturn) | None => true,
+        }
+    }
+
+    /// Whether the split is active at the current position of the script
+    pub fn is_active(&self, after_op_return: bool) -> bool {
+        after_op_return || self.scope != Some(Scope::AfterOpReturn)
+    }
 }
 
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+/// Restricts a SplitConfig to part of the transaction, so that e.g. a `|` inside
+/// a signature or pubkey push does not split input tapes
+pub enum Scope {
+    /// Unlocking scripts only
+    Inputs,
+    /// Locking scripts only
+    Outputs,
+    /// Locking scripts of the outputs at these indices only
+    OutputIndices(Vec<usize>),
+    /// Only the part of a script after its first `OP_RETURN`
+    AfterOpReturn,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+/// Decides whether a push is emitted in a given encoding
+pub enum EncodingRule {
+    Always,
+    Never,
+    /// Only when the push is valid UTF-8
+    Utf8,
+    /// Only when the push length (in bytes) is one of these, e.g. `[20, 32]` for hashes
+    Lengths(Vec<usize>),
+}
+
+impl EncodingRule {
+    pub fn applies(&self, bytes: &[u8]) -> bool {
+        match self {
+            EncodingRule::Always => true,
+            EncodingRule::Never => false,
+            EncodingRule::Utf8 => std::str::from_utf8(bytes).is_ok(),
+            EncodingRule::Lengths(lengths) => lengths.contains(&bytes.len()),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+/// Which of `b` (base64), `s` (UTF-8) and `h` (hex) are emitted for each data Cell.
+/// Large pushes use the same rules for `lb`, `ls` and `lh`.
+pub struct Encoding {
+    pub b: EncodingRule,
+    pub s: EncodingRule,
+    pub h: EncodingRule,
+}
+
+impl Default for Encoding {
+    fn default() -> Self {
+        Self {
+            b: EncodingRule::Always,
+            s: EncodingRule::Always,
+            h: EncodingRule::Never,
+        }
+    }
+}
+
+/// Transforms each Cell before it is added to a Tape.
+///
+/// Implemented for any `Fn(Cell, &ScriptBit) -> Cell + Send + Sync`, so closures
+/// can capture state (thresholds, counters, store handles) and a single
+/// `ParseConfig` can be shared across threads.
+pub trait CellTransform: Send + Sync {
+    fn transform(&self, cell: Cell, script_bit: &bsv::ScriptBit) -> Cell;
+}
+
+impl<F> CellTransform for F
+where
+    F: Fn(Cell, &bsv::ScriptBit) -> Cell + Send + Sync,
+{
+    fn transform(&self, cell: Cell, script_bit: &bsv::ScriptBit) -> Cell {
+        self(cell, script_bit)
+    }
+}
+
+impl std::fmt::Debug for dyn CellTransform {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("CellTransform")
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(default)]
+/// Deserializes from the JS BPU config format, e.g.
+/// `{"split":[{"token":{"s":"|"},"include":"l"}]}`
 pub struct ParseConfig {
     pub split: Vec<SplitConfig>,
-    pub transform: Option<fn(Cell, &bsv::ScriptBit) -> Cell>,
+    #[serde(skip)]
+    pub transform: Option<Arc<dyn CellTransform>>,
+    /// Pushes larger than this many bytes are stored in `lb`/`ls` instead of `b`/`s`,
+    /// keeping documents small for Mongo-style backends (JS BOB uses 512)
+    pub large_push_threshold: Option<usize>,
+    /// Which encodings are emitted for each data Cell (`b` and `s` by default)
+    pub encoding: Encoding,
+    /// Offloads pushes over a size limit to a BlobStore, setting `f`/`lf` to their hash
+    #[serde(skip)]
+    pub blob: Option<BlobConfig>,
+    /// Keeps OP_IF/OP_NOTIF branches as child Tapes (`pass`/`fail`) of the conditional Cell
+    /// instead of flattening them into the parent Tape
+    pub nested: bool,
+    /// Records the push opcode (`push`) and byte length (`len`) of each data Cell
+    pub push_info: bool,
+    /// Decodes small pushes and `OP_1NEGATE`/`OP_0`..`OP_16` into `n`, following Bitcoin
+    /// script number rules
+    pub numbers: bool,
+    /// Network used to derive addresses (mainnet by default)
+    pub network: Network,
+    /// How a missing address is represented (the string `"false"` by default)
+    pub address_mode: AddressMode,
+    /// Looks up spent outputs to fill input values and exact addresses
+    #[serde(skip)]
+    pub prev_outs: Option<Arc<dyn PrevOutProvider>>,
+    /// Computes `BPU::stats` (size, totals, fee and fee rate)
+    pub stats: bool,
+    /// Labels each Tape with the protocol of its Bitcom prefix
+    #[serde(skip)]
+    pub bitcom: Option<Arc<BitcomRegistry>>,
+}
+
+impl ParseConfig {
+    /// Builds a data Cell (w/o index) from a push made with the `push` opcode
+    pub fn data_cell(&self, bytes: Vec<u8>, string: String, push: Option<u8>) -> Result<Cell> {
+        let encoding = &self.encoding;
+        let large = matches!(self.large_push_threshold, Some(threshold) if bytes.len() > threshold);
+
+        let meta = Cell {
+            push: push.filter(|_| self.push_info),
+            len: self.push_info.then_some(bytes.len()),
+            n: self.numbers.then(|| decode_script_num(&bytes)).flatten(),
+            ..Default::default()
+        };
+
+        if let Some(blob) = self.blob.as_ref().filter(|blob| bytes.len() > blob.limit) {
+            let hash = blob.store.put(&bytes)?;
+
+            return Ok(match large {
+                true => Cell {
+                    lf: Some(hash),
+                    ..meta
+                },
+                false => Cell {
+                    f: Some(hash),
+                    ..meta
+                },
+            });
+        }
+
+        let s = encoding.s.applies(&bytes).then_some(string);
+        let h = encoding.h.applies(&bytes).then(|| hex::encode(&bytes));
+
+        Ok(match large {
+            true => Cell {
+                lb: encoding
+                    .b
+                    .applies(&bytes)
+                    .then(|| base64::engine::general_purpose::STANDARD.encode(&bytes)),
+                ls: s,
+                lh: h,
+                ..meta
+            },
+            false => Cell {
+                b: encoding.b.applies(&bytes).then_some(bytes),
+                s,
+                h,
+                ..meta
+            },
+        })
+    }
+}
+
+/// Decodes a minimally encoded script number (little-endian sign-magnitude) of up to 8 bytes
+pub fn decode_script_num(bytes: &[u8]) -> Option<i64> {
+    let Some((&last, rest)) = bytes.split_last() else {
+        return Some(0);
+    };
+
+    if bytes.len() > 8 {
+        return None;
+    }
+
+    // Minimal encoding: the last byte may only be 0x00/0x80 when the previous byte
+    // needs its high bit for the magnitude
+    if last & 0x7f == 0 && !rest.last().is_some_and(|byte| byte & 0x80 != 0) {
+        return None;
+    }
+
+    let magnitude = bytes
+        .iter()
+        .enumerate()
+        .fold(0u64, |acc, (i, byte)| acc | ((*byte as u64) << (8 * i)))
+        & !(0x80u64 << (8 * rest.len()));
+
+    let magnitude = magnitude as i64;
+    Some(if last & 0x80 != 0 {
+        -magnitude
+    } else {
+        magnitude
+    })
+}
+
+/// Value pushed by a small-integer opcode (`OP_0`, `OP_1NEGATE`, `OP_1`..`OP_16`)
+pub fn decode_small_int(op: u8) -> Option<i64> {
+    match op {
+        0x00 => Some(0),
+        0x4f => Some(-1),
+        0x51..=0x60 => Some(op as i64 - 0x50),
+        _ => None,
+    }
 }
 
 #[serde_as]
 #[skip_serializing_none]
-#[derive(Debug, Serialize, Deserialize, Default)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct Tape {
     pub cell: Vec<Cell>,
     pub i: usize,
+    /// Protocol name of the Tape's Bitcom prefix (see `ParseConfig::bitcom`)
+    pub protocol: Option<String>,
 }
 
 #[serde_as]
@@ -145,6 +529,17 @@ pub struct Cell {
     pub lh: Option<String>,
     pub lf: Option<String>,
     pub lb: Option<String>,
+    /// Opcode used to push the data: the length byte for direct pushes (`0x01`-`0x4b`),
+    /// or `OP_PUSHDATA1`/`OP_PUSHDATA2`/`OP_PUSHDATA4` (`push_info` mode only)
+    pub push: Option<u8>,
+    /// Exact byte length of the pushed data (`push_info` mode only)
+    pub len: Option<usize>,
+    /// Script number value of small pushes and `OP_1NEGATE`/`OP_0`..`OP_16` (numbers mode only)
+    pub n: Option<i64>,
+    /// Branch taken when the condition holds (nested mode only)
+    pub pass: Option<Vec<Tape>>,
+    /// Branch after OP_ELSE (nested mode only)
+    pub fail: Option<Vec<Tape>>,
 }
 
 #[serde_as]
@@ -154,7 +549,67 @@ pub struct SendRecv {
     pub h: Option<String>,
     pub i: u32,
     pub v: Option<u64>,
-    pub a: Option<String>,
+    pub a: Option<Address>,
+    /// Hash160 (hex) the address was derived from
+    pub hash160: Option<String>,
+    /// Public key (hex) the address was derived from, if any
+    pub pubkey: Option<String>,
+}
+
+impl SendRecv {
+    /// Sets the address (following `address_mode`) and the key material it derives from
+    pub fn set_address(&mut self, key: Option<AddressKey>, parse_config: &ParseConfig) {
+        (self.a, self.hash160, self.pubkey) = address_fields(key, parse_config);
+    }
+}
+
+/// `a`, `hash160` and `pubkey` of a SendRecv, following `address_mode`
+pub(crate) fn address_fields(
+    key: Option<AddressKey>,
+    parse_config: &ParseConfig,
+) -> (Option<Address>, Option<String>, Option<String>) {
+    let address = key
+        .as_ref()
+        .and_then(|key| key.address(parse_config.network));
+
+    (
+        parse_config.address_mode.resolve(address),
+        key.as_ref().map(|key| hex::encode(&key.hash160)),
+        key.and_then(|key| key.pubkey).map(hex::encode),
+    )
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(untagged)]
+/// Value of `SendRecv.a`
+pub enum Address {
+    Value(String),
+    /// JSON `false` when no address was found (`AddressMode::False`)
+    False(bool),
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+/// How a missing address is represented in `SendRecv.a`
+pub enum AddressMode {
+    /// `a` is the string `"false"`, as in earlier versions and the JS BPU
+    #[default]
+    Legacy,
+    /// `a` is omitted
+    Omit,
+    /// `a` is JSON `false`
+    False,
+}
+
+impl AddressMode {
+    pub fn resolve(&self, address: Option<String>) -> Option<Address> {
+        match (address, self) {
+            (Some(address), _) => Some(Address::Value(address)),
+            (None, AddressMode::Omit) => None,
+            (None, AddressMode::False) => Some(Address::False(false)),
+            (None, AddressMode::Legacy) => Some(Address::Value("false".to_string())),
+        }
+    }
 }
 
 #[serde_as]
@@ -166,6 +621,11 @@ pub struct IO {
     pub tape: Vec<Tape>,
     pub e: Option<SendRecv>,
     pub seq: Option<u32>,
+    /// Locking script type (outputs only)
+    pub script_type: Option<ScriptType>,
+    /// Chunk count of the script, recorded only when its last chunks were discarded
+    /// by a split (`include: None`) so they can be restored
+    pub chunks: Option<usize>,
 }
 
 impl IO {
@@ -183,6 +643,8 @@ pub struct IndexCounter {
     pub tape_index: usize,
     pub cell_index: usize,
     pub chunk_index: usize,
+    /// Set once the first `OP_RETURN` of the script has been seen
+    pub op_return: bool,
 }
 
 impl IndexCounter {
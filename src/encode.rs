@@ -0,0 +1,229 @@
+use crate::components::*;
+use crate::BPU;
+use base64::Engine;
+use bsv::{OpCodes, Script, ScriptBit, Transaction, TxIn, TxOut};
+use num_traits::FromPrimitive;
+use std::str::FromStr;
+
+impl BPU {
+    /// Reassembles the original Transaction from its Tapes and Cells.
+    ///
+    /// `parse_config` must be the config the BPU was parsed with, so that delimiters
+    /// discarded by a split (`include: None`) can be put back. Fails rather than
+    /// returning a different transaction when the result does not hash to `tx.h`.
+    ///
+    /// Data Cells are re-pushed minimally unless they carry `push`, so scripts with
+    /// non-minimal pushes are only reproduced byte-exact when parsed with `push_info` on.
+    pub fn to_transaction(&self, parse_config: &ParseConfig) -> Result<Transaction> {
+        let mut tx = Transaction::new(self.tx.v.unwrap_or(1), self.lock.unwrap_or(0));
+
+        for (i, input) in self.r#in.iter().enumerate() {
+            let e = input
+                .e
+                .as_ref()
+                .ok_or_else(|| BpuError::CustomError(format!("Input {} has no outpoint", i)))?;
+
+            let prev_tx_id = e
+                .h
+                .as_ref()
+                .ok_or_else(|| BpuError::CustomError(format!("Input {} has no prev txid", i)))?;
+
+            let script = Self::to_script(input, true, parse_config)?;
+
+            tx.add_input(&TxIn::new(
+                &hex::decode(prev_tx_id).map_err(|err| BpuError::CustomError(err.to_string()))?,
+                e.i,
+                &script,
+                input.seq,
+            ));
+        }
+
+        for (i, output) in self.r#out.iter().enumerate() {
+            let satoshis = output
+                .e
+                .as_ref()
+                .and_then(|e| e.v)
+                .ok_or_else(|| BpuError::CustomError(format!("Output {} has no value", i)))?;
+
+            let script = Self::to_script(output, false, parse_config)?;
+
+            tx.add_output(&TxOut::new(satoshis, &script));
+        }
+
+        if let Some(txid) = &self.tx.h {
+            if &tx.get_id_hex()? != txid {
+                return Err(BpuError::CustomError(format!(
+                    "Reassembled transaction does not match {}",
+                    txid
+                )));
+            }
+        }
+
+        Ok(tx)
+    }
+
+    /// Reassembles the original raw transaction (hex)
+    pub fn to_raw_tx(&self, parse_config: &ParseConfig) -> Result<String> {
+        Ok(self.to_transaction(parse_config)?.to_hex()?)
+    }
+
+    /// Rebuilds an Input/Output script, filling gaps in `ii` (and up to `chunks`)
+    /// with discarded delimiters
+    fn to_script(xput: &IO, is_input: bool, parse_config: &ParseConfig) -> Result<Script> {
+        let mut cells: Vec<&Cell> = vec![];
+        collect_cells(&xput.tape, &mut cells);
+        cells.sort_by_key(|cell| cell.ii);
+
+        let mut bits: Vec<ScriptBit> = vec![];
+
+        for cell in cells {
+            while bits.len() < cell.ii {
+                bits.push(Self::discarded_delimiter(xput, is_input, parse_config)?);
+            }
+
+            bits.push(cell.to_script_bit()?);
+        }
+
+        while bits.len() < xput.chunks.unwrap_or(0) {
+            bits.push(Self::discarded_delimiter(xput, is_input, parse_config)?);
+        }
+
+        Ok(Script::from_script_bits(bits))
+    }
+
+    /// The ScriptBit a discarding split removed from this Input/Output, if unambiguous
+    fn discarded_delimiter(
+        xput: &IO,
+        is_input: bool,
+        parse_config: &ParseConfig,
+    ) -> Result<ScriptBit> {
+        let mut candidates = parse_config
+            .split
+            .iter()
+            .filter(|setting| setting.include.is_none() && setting.applies_to(is_input, xput.i))
+            .map(|setting| setting.token.to_script_bit());
+
+        match (candidates.next(), candidates.next()) {
+            (Some(Some(bit)), None) => Ok(bit),
+            _ => Err(BpuError::CustomError(format!(
+                "Cannot recover discarded delimiter in {} {}",
+                if is_input { "input" } else { "output" },
+                xput.i
+            ))),
+        }
+    }
+}
+
+impl Token {
+    /// The exact ScriptBit this token matches (`None` for pattern tokens)
+    pub fn to_script_bit(&self) -> Option<ScriptBit> {
+        if let Some(op) = self.op {
+            return OpCodes::from_u8(op).map(ScriptBit::OpCode);
+        }
+
+        if let Some(ops) = &self.ops {
+            return OpCodes::from_str(ops).ok().map(ScriptBit::OpCode);
+        }
+
+        self.b
+            .clone()
+            .or_else(|| self.s.as_ref().map(|s| s.as_bytes().to_vec()))
+            .map(push_script_bit)
+    }
+}
+
+impl Cell {
+    /// Push data recovered from whichever encoding the Cell carries
+    pub fn data(&self) -> Result<Option<Vec<u8>>> {
+        let base64 = base64::engine::general_purpose::STANDARD;
+
+        if let Some(b) = &self.b {
+            return Ok(Some(b.clone()));
+        }
+
+        if let Some(lb) = &self.lb {
+            return base64
+                .decode(lb)
+                .map(Some)
+                .map_err(|e| BpuError::CustomError(e.to_string()));
+        }
+
+        if let Some(h) = self.h.as_ref().or(self.lh.as_ref()) {
+            return hex::decode(h)
+                .map(Some)
+                .map_err(|e| BpuError::CustomError(e.to_string()));
+        }
+
+        if self.f.is_some() || self.lf.is_some() {
+            return Err(BpuError::CustomError(format!(
+                "Cell {} was offloaded to a BlobStore",
+                self.ii
+            )));
+        }
+
+        Ok(self
+            .s
+            .as_ref()
+            .or(self.ls.as_ref())
+            .map(|s| s.as_bytes().to_vec()))
+    }
+
+    pub fn to_script_bit(&self) -> Result<ScriptBit> {
+        if let Some(op) = self.op {
+            return OpCodes::from_u8(op)
+                .map(ScriptBit::OpCode)
+                .ok_or_else(|| BpuError::CustomError(format!("Unknown opcode {}", op)));
+        }
+
+        let bytes = self.data()?.ok_or_else(|| {
+            BpuError::CustomError(format!(
+                "Cell {} has neither an opcode nor push data",
+                self.ii
+            ))
+        })?;
+
+        if self.len.is_some_and(|len| len != bytes.len()) {
+            return Err(BpuError::CustomError(format!(
+                "Cell {} data does not match its recorded length",
+                self.ii
+            )));
+        }
+
+        // Reproduce the original (possibly non-minimal) push when it was recorded
+        match self.push {
+            Some(op @ 0x01..=0x4b) if op as usize == bytes.len() => Ok(ScriptBit::Push(bytes)),
+            Some(op) => match OpCodes::from_u8(op) {
+                Some(
+                    code @ (OpCodes::OP_PUSHDATA1 | OpCodes::OP_PUSHDATA2 | OpCodes::OP_PUSHDATA4),
+                ) => Ok(ScriptBit::PushData(code, bytes)),
+                _ => Err(BpuError::CustomError(format!(
+                    "Cell {} has an invalid push opcode {}",
+                    self.ii, op
+                ))),
+            },
+            None => Ok(push_script_bit(bytes)),
+        }
+    }
+}
+
+/// Gathers Cells from Tapes, including the branches of nested conditionals
+pub(crate) fn collect_cells<'a>(tapes: &'a [Tape], cells: &mut Vec<&'a Cell>) {
+    for cell in tapes.iter().flat_map(|tape| &tape.cell) {
+        cells.push(cell);
+
+        for branch in [&cell.pass, &cell.fail].into_iter().flatten() {
+            collect_cells(branch, cells);
+        }
+    }
+}
+
+/// Minimally encoded push of `bytes`
+pub fn push_script_bit(bytes: Vec<u8>) -> ScriptBit {
+    match bytes.len() {
+        0 => ScriptBit::OpCode(OpCodes::OP_0),
+        1..=0x4b => ScriptBit::Push(bytes),
+        0x4c..=0xff => ScriptBit::PushData(OpCodes::OP_PUSHDATA1, bytes),
+        0x100..=0xffff => ScriptBit::PushData(OpCodes::OP_PUSHDATA2, bytes),
+        _ => ScriptBit::PushData(OpCodes::OP_PUSHDATA4, bytes),
+    }
+}
@@ -4,5 +4,32 @@ pub use components::*;
 pub mod bpu;
 pub use bpu::*;
 
+pub mod encode;
+pub use encode::*;
+
+pub mod bpu_ref;
+pub use bpu_ref::*;
+
+pub mod network;
+pub use network::*;
+
+pub mod script_type;
+pub use script_type::*;
+
+pub mod reader;
+pub use reader::*;
+
+pub mod block;
+pub use block::*;
+
+pub mod blk_files;
+pub use blk_files::*;
+
+pub mod prevout;
+pub use prevout::*;
+
+pub mod blob;
+pub use blob::*;
+
 pub mod protocols;
 pub use protocols::*;
@@ -0,0 +1,47 @@
+use bsv::Hash;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+/// Network the transactions belong to, used for address derivation
+pub enum Network {
+    #[default]
+    Mainnet,
+    Testnet,
+    Regtest,
+}
+
+impl Network {
+    /// Version byte of P2PKH addresses
+    pub fn p2pkh_version(&self) -> u8 {
+        match self {
+            Network::Mainnet => 0x00,
+            Network::Testnet | Network::Regtest => 0x6f,
+        }
+    }
+
+    /// Bytes prefixing each block in the node's `blk*.dat` files
+    pub fn disk_magic(&self) -> [u8; 4] {
+        match self {
+            Network::Mainnet => [0xf9, 0xbe, 0xb4, 0xd9],
+            Network::Testnet => [0x0b, 0x11, 0x09, 0x07],
+            Network::Regtest => [0xfa, 0xbf, 0xb5, 0xda],
+        }
+    }
+
+    /// Base58Check P2PKH address of a 20 byte pubkey hash
+    pub fn p2pkh_address(&self, pubkey_hash: &[u8]) -> Option<String> {
+        if pubkey_hash.len() != 20 {
+            return None;
+        }
+
+        let mut payload = Vec::with_capacity(25);
+        payload.push(self.p2pkh_version());
+        payload.extend_from_slice(pubkey_hash);
+
+        let checksum = Hash::sha_256d(&payload).to_bytes();
+        payload.extend_from_slice(&checksum[..4]);
+
+        Some(bs58::encode(payload).into_string())
+    }
+}
@@ -0,0 +1,100 @@
+use crate::Result;
+use bsv::{Script, Transaction};
+use std::collections::HashMap;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+
+#[derive(Debug, Clone)]
+/// The output an input spends
+pub struct PrevOut {
+    pub satoshis: u64,
+    pub script: Script,
+}
+
+/// Looks up the outputs spent by transaction inputs, so their values and exact
+/// addresses can be filled in
+pub trait PrevOutProvider: Send + Sync {
+    /// Returns the output at `txid:vout` (txid as display hex), if known
+    fn prev_out(&self, txid: &str, vout: u32) -> Result<Option<PrevOut>>;
+}
+
+impl std::fmt::Debug for dyn PrevOutProvider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("PrevOutProvider")
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+/// Previous outputs held in memory
+pub struct MemoryPrevOuts {
+    outputs: HashMap<(String, u32), PrevOut>,
+}
+
+impl MemoryPrevOuts {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, txid: impl Into<String>, vout: u32, prev_out: PrevOut) {
+        self.outputs.insert((txid.into(), vout), prev_out);
+    }
+
+    /// Adds every output of `tx`
+    pub fn add_transaction(&mut self, tx: &Transaction) -> Result<()> {
+        let txid = tx.get_id_hex()?;
+
+        for (vout, output) in (0..tx.get_noutputs()).filter_map(|e| Some((e, tx.get_output(e)?))) {
+            self.insert(
+                txid.clone(),
+                vout as u32,
+                PrevOut {
+                    satoshis: output.get_satoshis(),
+                    script: output.get_script_pub_key(),
+                },
+            );
+        }
+
+        Ok(())
+    }
+}
+
+impl PrevOutProvider for MemoryPrevOuts {
+    fn prev_out(&self, txid: &str, vout: u32) -> Result<Option<PrevOut>> {
+        Ok(self.outputs.get(&(txid.to_string(), vout)).cloned())
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Reads previous transactions from `<dir>/<txid>`, stored as raw bytes or hex
+pub struct DirPrevOuts {
+    pub dir: PathBuf,
+}
+
+impl DirPrevOuts {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+}
+
+impl PrevOutProvider for DirPrevOuts {
+    fn prev_out(&self, txid: &str, vout: u32) -> Result<Option<PrevOut>> {
+        let bytes = match fs::read(self.dir.join(txid)) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+
+        let tx = match std::str::from_utf8(&bytes).map(str::trim) {
+            Ok(hex) if hex.bytes().all(|byte| byte.is_ascii_hexdigit()) => {
+                Transaction::from_hex(hex)?
+            }
+            _ => Transaction::from_bytes(&bytes)?,
+        };
+
+        Ok(tx.get_output(vout as usize).map(|output| PrevOut {
+            satoshis: output.get_satoshis(),
+            script: output.get_script_pub_key(),
+        }))
+    }
+}
@@ -1 +1,175 @@
+use crate::BMapManager;
+use crate::ProtocolHandler;
+use crate::Result;
+use crate::Tape;
+use crate::BPU;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
 
+/// Handles the Tapes of one Bitcom protocol.
+///
+/// Implemented for any `Fn(&Tape, &mut BMapManager) -> Result<()> + Send + Sync`.
+pub trait BitcomHandler: Send + Sync {
+    fn handle(&self, tape: &Tape, bmap: &mut BMapManager) -> Result<()>;
+}
+
+impl<F> BitcomHandler for F
+where
+    F: Fn(&Tape, &mut BMapManager) -> Result<()> + Send + Sync,
+{
+    fn handle(&self, tape: &Tape, bmap: &mut BMapManager) -> Result<()> {
+        self(tape, bmap)
+    }
+}
+
+impl std::fmt::Debug for dyn BitcomHandler {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("BitcomHandler")
+    }
+}
+
+#[derive(Debug, Clone)]
+/// A registered Bitcom protocol
+pub struct BitcomProtocol {
+    pub name: String,
+    pub handler: Option<Arc<dyn BitcomHandler>>,
+}
+
+/// Bitcom prefixes (the first push of a Tape) of well known protocols
+pub const KNOWN_PREFIXES: &[(&str, &str)] = &[
+    ("19HxigV4QyBv3tHpQVcUEQyq1pzZVdoAut", "B"),
+    ("1PuQa7K62MiKCtssSLKy1kh56WWU7MtUR5", "MAP"),
+    ("15PciHG22SNLQJXMoSUaWVi7WSqc7hCfva", "AIP"),
+    ("1BAPSuaPnfGnSBM3GLV9yhxUdYe4vGbdMT", "BAP"),
+    ("1HA1P2exomAwCUycZHr8WeyFoy5vuQASE3", "HAIP"),
+    ("1signyCizp1VyBsJ5Ss2tEAgw7zCYNJu4", "PSP"),
+    ("15DHFxWZJT58f9nhyGnsRBqrgwK4W6h4Up", "BCAT"),
+    ("1ChDHzdd1H4wSjgGMHyndZm6qxEDGjqpJL", "BCAT_PART"),
+    ("meta", "METANET"),
+    ("$", "BITCOM"),
+];
+
+#[derive(Debug)]
+/// Maps Bitcom prefixes to protocol names and handlers.
+/// Protocols can be registered at runtime, also while the registry is shared.
+pub struct BitcomRegistry {
+    protocols: RwLock<HashMap<String, BitcomProtocol>>,
+}
+
+impl Default for BitcomRegistry {
+    /// Registry with the `KNOWN_PREFIXES` (without handlers)
+    fn default() -> Self {
+        let registry = Self::empty();
+        for (prefix, name) in KNOWN_PREFIXES {
+            registry.register(*prefix, *name, None);
+        }
+        registry
+    }
+}
+
+impl BitcomRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registry without any protocols
+    pub fn empty() -> Self {
+        Self {
+            protocols: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Registers (or replaces) the protocol for `prefix`
+    pub fn register(
+        &self,
+        prefix: impl Into<String>,
+        name: impl Into<String>,
+        handler: Option<Arc<dyn BitcomHandler>>,
+    ) {
+        let protocol = BitcomProtocol {
+            name: name.into(),
+            handler,
+        };
+
+        self.protocols
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(prefix.into(), protocol);
+    }
+
+    pub fn unregister(&self, prefix: &str) -> Option<BitcomProtocol> {
+        self.protocols
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(prefix)
+    }
+
+    pub fn get(&self, prefix: &str) -> Option<BitcomProtocol> {
+        self.protocols
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(prefix)
+            .cloned()
+    }
+
+    /// Bitcom prefix of a Tape: its first push, as UTF-8 (`None` when that push
+    /// was offloaded to a BlobStore)
+    pub fn prefix(tape: &Tape) -> Option<String> {
+        let bytes = tape
+            .cell
+            .iter()
+            .find(|cell| cell.op.is_none())?
+            .data()
+            .ok()
+            .flatten()?;
+        String::from_utf8(bytes).ok()
+    }
+
+    /// Protocol of a Tape, by its Bitcom prefix
+    pub fn protocol(&self, tape: &Tape) -> Option<BitcomProtocol> {
+        self.get(&Self::prefix(tape)?)
+    }
+
+    /// Sets `Tape.protocol` on each Tape with a registered prefix
+    pub fn label(&self, tapes: &mut [Tape]) {
+        for tape in tapes {
+            tape.protocol = self.protocol(tape).map(|protocol| protocol.name);
+        }
+    }
+
+    /// Runs the handler of each Tape's protocol
+    pub fn handle(&self, tapes: &[Tape], bmap: &mut BMapManager) -> Result<()> {
+        for tape in tapes {
+            if let Some(handler) = self.protocol(tape).and_then(|protocol| protocol.handler) {
+                handler.handle(tape, bmap)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl ProtocolHandler for BitcomRegistry {
+    fn name(&self) -> &str {
+        "bitcom"
+    }
+
+    fn detect(&self, bpu: &BPU) -> bool {
+        bpu.r#out
+            .iter()
+            .flat_map(|output| &output.tape)
+            .any(|tape| {
+                self.protocol(tape)
+                    .is_some_and(|protocol| protocol.handler.is_some())
+            })
+    }
+
+    /// Runs the handler of each output Tape's Bitcom protocol
+    fn parse(&self, bpu: &BPU, bmap: &mut BMapManager) -> Result<()> {
+        for output in &bpu.r#out {
+            self.handle(&output.tape, bmap)?;
+        }
+
+        Ok(())
+    }
+}
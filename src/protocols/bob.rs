@@ -1 +1,78 @@
+use crate::Block;
+use crate::BobTx;
+use crate::Include;
+use crate::ParseConfig;
+use crate::Result;
+use crate::SplitConfig;
+use crate::Tx;
+use crate::BPU;
 
+pub struct Bob;
+
+impl Bob {
+    /// Standard BOB split rules: a Tape ends at `OP_RETURN` (kept on its left) and each `|`
+    /// gets a Tape of its own. Pushes over 512 bytes go to `lb`/`ls`, like the original indexer
+    pub fn config() -> ParseConfig {
+        ParseConfig {
+            split: vec![
+                SplitConfig {
+                    include: Some(Include::Left),
+                    token: crate::Token {
+                        op: Some(106),
+                        ..Default::default()
+                    },
+                    scope: None,
+                },
+                SplitConfig {
+                    include: Some(Include::Center),
+                    token: crate::Token {
+                        s: Some("|".to_string()),
+                        ..Default::default()
+                    },
+                    scope: None,
+                },
+            ],
+            large_push_threshold: Some(512),
+            ..Default::default()
+        }
+    }
+
+    pub fn from_raw_tx(hex: &str, blk: Option<Block>, mem: Option<u64>) -> Result<BobTx> {
+        let bpu = BPU::from_raw_tx(hex, Self::config())?;
+        Ok(Self::from_bpu(bpu, blk, mem))
+    }
+
+    pub fn from_bytes(raw_tx: &[u8], blk: Option<Block>, mem: Option<u64>) -> Result<BobTx> {
+        let bpu = BPU::from_bytes(raw_tx, Self::config())?;
+        Ok(Self::from_bpu(bpu, blk, mem))
+    }
+
+    /// Wraps a BPU parsed with `Bob::config()`, dropping the fields the original
+    /// BOB indexer does not emit (version, locktime, sequence numbers, script types
+    /// and address key material)
+    pub fn from_bpu(bpu: BPU, blk: Option<Block>, mem: Option<u64>) -> BobTx {
+        let mut xputs = [bpu.r#in, bpu.r#out];
+        for xput in xputs.iter_mut().flatten() {
+            xput.seq = None;
+            xput.script_type = None;
+            if let Some(e) = xput.e.as_mut() {
+                e.hash160 = None;
+                e.pubkey = None;
+            }
+        }
+        let [r#in, r#out] = xputs;
+
+        BobTx {
+            blk,
+            mem,
+            fields: Default::default(),
+            r#out,
+            r#in,
+            tx: Tx {
+                h: bpu.tx.h,
+                ..Default::default()
+            },
+            lock: None,
+        }
+    }
+}
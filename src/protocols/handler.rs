@@ -0,0 +1,63 @@
+use crate::BMapManager;
+use crate::Result;
+use crate::BPU;
+use std::sync::Arc;
+
+/// A protocol that can be detected in, and parsed from, the Tapes of a BPU
+pub trait ProtocolHandler: Send + Sync {
+    fn name(&self) -> &str;
+
+    /// Whether the BPU carries this protocol
+    fn detect(&self, bpu: &BPU) -> bool;
+
+    /// Parses the protocol from the BPU's Tapes into `bmap`
+    fn parse(&self, bpu: &BPU, bmap: &mut BMapManager) -> Result<()>;
+}
+
+impl std::fmt::Debug for dyn ProtocolHandler {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "ProtocolHandler({})", self.name())
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+/// Runs every registered ProtocolHandler over a BPU
+pub struct ProtocolPipeline {
+    handlers: Vec<Arc<dyn ProtocolHandler>>,
+}
+
+impl ProtocolPipeline {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Handlers run in registration order
+    pub fn register(&mut self, handler: Arc<dyn ProtocolHandler>) -> &mut Self {
+        self.handlers.push(handler);
+        self
+    }
+
+    pub fn with(mut self, handler: Arc<dyn ProtocolHandler>) -> Self {
+        self.handlers.push(handler);
+        self
+    }
+
+    pub fn handlers(&self) -> &[Arc<dyn ProtocolHandler>] {
+        &self.handlers
+    }
+
+    /// Parses each detected protocol into `bmap`
+    pub fn run_into(&self, bpu: &BPU, bmap: &mut BMapManager) -> Result<()> {
+        for handler in self.handlers.iter().filter(|handler| handler.detect(bpu)) {
+            handler.parse(bpu, bmap)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn run(&self, bpu: &BPU) -> Result<BMapManager> {
+        let mut bmap = BMapManager::default();
+        self.run_into(bpu, &mut bmap)?;
+        Ok(bmap)
+    }
+}
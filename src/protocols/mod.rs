@@ -1,3 +1,6 @@
+pub mod handler;
+pub use handler::*;
+
 pub mod bob;
 pub use bob::*;
 
@@ -1,45 +1,59 @@
+use crate::encode::collect_cells;
 use crate::BMapManager;
 use crate::BpuError;
+use crate::Cell;
 use crate::Include;
+use crate::OrdChunk;
 use crate::OrdData;
 use crate::ParseConfig;
+use crate::ProtocolHandler;
 use crate::Result;
 use crate::SplitConfig;
 use crate::BPU;
+use crate::IO;
 use bsv::Script;
 use bsv::{OpCodes, ScriptBit, Transaction};
 
 pub struct Ord;
 
 impl Ord {
-    pub fn from_raw_tx(hex: &str) -> Result<BPU> {
-        let config = ParseConfig {
+    pub fn config() -> ParseConfig {
+        ParseConfig {
             split: vec![
                 SplitConfig {
                     include: Some(Include::Left),
                     token: crate::Token {
-                        op: None,
-                        ops: None,
-                        b: None,
                         s: Some("|".to_string()),
+                        ..Default::default()
                     },
+                    scope: None,
                 },
                 SplitConfig {
                     include: Some(Include::Left),
                     token: crate::Token {
                         op: Some(106),
-                        ops: None,
-                        b: None,
-                        s: None,
+                        ..Default::default()
                     },
+                    scope: None,
                 },
             ],
-            transform: None,
-        };
+            ..Default::default()
+        }
+    }
+
+    pub fn from_raw_tx(hex: &str) -> Result<BPU> {
+        BPU::from_raw_tx(hex, Self::config())
+    }
+
+    pub fn from_bytes(raw_tx: &[u8]) -> Result<BPU> {
+        BPU::from_bytes(raw_tx, Self::config())
+    }
 
-        BPU::from_raw_tx(hex, config)
+    pub fn from_reader<R: std::io::Read>(reader: &mut R) -> Result<BPU> {
+        BPU::from_reader(reader, Self::config())
     }
 
+    #[deprecated(note = "use `Ord` as a `ProtocolHandler`")]
     pub fn handler(tx: &Transaction, bmap: &mut BMapManager) -> Result<()> {
         let (_, ret) = Self::get_ord_script(tx)?;
         let op_if = ret.get_script_bit(0).unwrap();
@@ -75,7 +89,11 @@ impl Ord {
             }
 
             if let (Some(data), Some(content_type)) = (data, content_type) {
-                bmap.ord.push(OrdData { data, content_type });
+                bmap.ord.push(OrdData {
+                    data,
+                    content_type,
+                    chunks: None,
+                });
             }
         }
 
@@ -126,4 +144,138 @@ impl Ord {
             false
         }
     }
+
+    /// Cells of an output in script order, including nested branches
+    fn output_cells(output: &IO) -> Vec<&Cell> {
+        let mut cells = vec![];
+        collect_cells(&output.tape, &mut cells);
+        cells.sort_by_key(|cell| cell.ii);
+        cells
+    }
+
+    /// Position of the `ord` push of an `OP_0 OP_IF "ord"` envelope
+    fn envelope(cells: &[&Cell]) -> Result<Option<usize>> {
+        for (start, window) in cells.windows(3).enumerate() {
+            if tag(window[0])? == Some(0)
+                && window[1].op == Some(OpCodes::OP_IF as u8)
+                && push_data(window[2])?.as_deref() == Some(b"ord".as_slice())
+            {
+                return Ok(Some(start + 2));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Parses the first inscription envelope in `cells`. The body may span several pushes;
+    /// pushes offloaded to a BlobStore are kept as their hash in `chunks`.
+    pub fn inscription(cells: &[&Cell]) -> Result<Option<OrdData>> {
+        let Some(start) = Self::envelope(cells)? else {
+            return Ok(None);
+        };
+
+        let mut content_type = None;
+        let mut body = None;
+
+        let mut fields = cells[start + 1..]
+            .iter()
+            .take_while(|cell| cell.op != Some(OpCodes::OP_ENDIF as u8));
+
+        while let Some(cell) = fields.next() {
+            match tag(cell)? {
+                Some(0) => {
+                    let mut chunks = vec![];
+                    for cell in fields.by_ref() {
+                        chunks.push(match cell.f.as_ref().or(cell.lf.as_ref()) {
+                            Some(hash) => OrdChunk::Blob(hash.clone()),
+                            None => OrdChunk::Data(cell.data()?.unwrap_or_default()),
+                        });
+                    }
+                    body = Some(chunks);
+                }
+                Some(1) => {
+                    content_type = match fields.next() {
+                        Some(value) => push_data(value)?
+                            .map(|bytes| String::from_utf8_lossy(&bytes).to_string()),
+                        None => None,
+                    };
+                }
+                // Other fields carry a single value
+                _ => {
+                    fields.next();
+                }
+            }
+        }
+
+        let (Some(chunks), Some(content_type)) = (body, content_type) else {
+            return Ok(None);
+        };
+
+        let offloaded = chunks
+            .iter()
+            .any(|chunk| matches!(chunk, OrdChunk::Blob(_)));
+
+        Ok(Some(match offloaded {
+            true => OrdData {
+                data: vec![],
+                content_type,
+                chunks: Some(chunks),
+            },
+            false => OrdData {
+                data: chunks
+                    .into_iter()
+                    .flat_map(|chunk| match chunk {
+                        OrdChunk::Data(bytes) => bytes,
+                        OrdChunk::Blob(_) => vec![],
+                    })
+                    .collect(),
+                content_type,
+                chunks: None,
+            },
+        }))
+    }
+}
+
+impl ProtocolHandler for Ord {
+    fn name(&self) -> &str {
+        "ord"
+    }
+
+    fn detect(&self, bpu: &BPU) -> bool {
+        bpu.r#out
+            .iter()
+            .any(|output| matches!(Self::envelope(&Self::output_cells(output)), Ok(Some(_))))
+    }
+
+    fn parse(&self, bpu: &BPU, bmap: &mut BMapManager) -> Result<()> {
+        for output in &bpu.r#out {
+            if let Some(ord) = Self::inscription(&Self::output_cells(output))? {
+                bmap.ord.push(ord);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Envelope field tag: `OP_0`..`OP_16`, or a push of a single byte
+fn tag(cell: &Cell) -> Result<Option<u8>> {
+    Ok(match cell.op {
+        Some(0) => Some(0),
+        Some(op @ 0x51..=0x60) => Some(op - 0x50),
+        Some(_) => None,
+        None => match push_data(cell)?.as_deref() {
+            Some([]) => Some(0),
+            Some([tag]) => Some(*tag),
+            _ => None,
+        },
+    })
+}
+
+/// Push data of a Cell, or `None` for opcodes and pushes offloaded to a BlobStore
+fn push_data(cell: &Cell) -> Result<Option<Vec<u8>>> {
+    match cell.f.is_some() || cell.lf.is_some() {
+        true => Ok(None),
+        false => cell.data(),
+    }
 }
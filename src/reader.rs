@@ -0,0 +1,67 @@
+use crate::Result;
+use std::io::{self, Read};
+
+/// Reads one serialized transaction from a stream, returning its raw bytes
+pub fn read_raw_tx<R: Read>(reader: &mut R) -> Result<Vec<u8>> {
+    let mut raw = vec![];
+
+    copy_exact(reader, &mut raw, 4)?; // version
+
+    let n_inputs = copy_varint(reader, &mut raw)?;
+    for _ in 0..n_inputs {
+        copy_exact(reader, &mut raw, 36)?; // outpoint
+        let script_len = copy_varint(reader, &mut raw)?;
+        copy_exact(reader, &mut raw, script_len)?;
+        copy_exact(reader, &mut raw, 4)?; // sequence
+    }
+
+    let n_outputs = copy_varint(reader, &mut raw)?;
+    for _ in 0..n_outputs {
+        copy_exact(reader, &mut raw, 8)?; // satoshis
+        let script_len = copy_varint(reader, &mut raw)?;
+        copy_exact(reader, &mut raw, script_len)?;
+    }
+
+    copy_exact(reader, &mut raw, 4)?; // locktime
+
+    Ok(raw)
+}
+
+/// Reads a Bitcoin varint from a stream
+pub fn read_varint<R: Read>(reader: &mut R) -> Result<u64> {
+    copy_varint(reader, &mut vec![])
+}
+
+/// Appends exactly `len` bytes from the stream to `raw`, growing it only as data arrives
+fn copy_exact<R: Read>(reader: &mut R, raw: &mut Vec<u8>, len: u64) -> Result<()> {
+    let read = reader.by_ref().take(len).read_to_end(raw)?;
+
+    if read as u64 != len {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            format!("expected {} bytes, got {}", len, read),
+        )
+        .into());
+    }
+
+    Ok(())
+}
+
+/// Appends a varint from the stream to `raw` and returns its value
+fn copy_varint<R: Read>(reader: &mut R, raw: &mut Vec<u8>) -> Result<u64> {
+    let start = raw.len();
+    copy_exact(reader, raw, 1)?;
+
+    let len = match raw[start] {
+        0xfd => 2,
+        0xfe => 4,
+        0xff => 8,
+        n => return Ok(n as u64),
+    };
+
+    copy_exact(reader, raw, len)?;
+
+    let mut bytes = [0u8; 8];
+    bytes[..len as usize].copy_from_slice(&raw[start + 1..]);
+    Ok(u64::from_le_bytes(bytes))
+}
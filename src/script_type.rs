@@ -0,0 +1,219 @@
+use crate::{flatten_script_bits, Network};
+use bsv::{Hash, OpCodes, PublicKey, ScriptBit};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+/// Locking script type, detected from standard templates
+pub enum ScriptType {
+    P2pkh,
+    P2pk,
+    P2sh,
+    Multisig,
+    OpFalseOpReturn,
+    OpReturn,
+    OrdinalInscription,
+    #[serde(rename = "1sat_p2pkh_with_inscription")]
+    OneSatP2pkhWithInscription,
+    Nonstandard,
+}
+
+#[derive(Debug, Clone)]
+/// A detected script type, with the key material its address derives from (if any)
+pub struct ScriptTemplate {
+    pub script_type: ScriptType,
+    pub pubkey_hash: Option<Vec<u8>>,
+    pub pubkey: Option<Vec<u8>>,
+}
+
+impl ScriptTemplate {
+    pub fn detect(script_bits: &[ScriptBit]) -> Self {
+        let bits = flatten_script_bits(script_bits);
+        let chunks: Vec<ScriptChunk> = bits.iter().map(script_chunk).collect();
+        Self::detect_chunks(&chunks)
+    }
+
+    /// Detects the template of a script read chunk by chunk (see `BpuRef`),
+    /// borrowing push data instead of copying it
+    pub fn detect_chunks(chunks: &[ScriptChunk]) -> Self {
+        let template = |script_type: ScriptType| Self {
+            script_type,
+            pubkey_hash: None,
+            pubkey: None,
+        };
+
+        if let Some(hash) = p2pkh_hash(chunks) {
+            return Self {
+                pubkey_hash: Some(hash.to_vec()),
+                ..template(ScriptType::P2pkh)
+            };
+        }
+
+        if let [(_, Some(pubkey)), checksig] = chunks {
+            if is_op(checksig, OpCodes::OP_CHECKSIG) && (pubkey.len() == 33 || pubkey.len() == 65) {
+                return Self {
+                    pubkey: Some(pubkey.to_vec()),
+                    ..template(ScriptType::P2pk)
+                };
+            }
+        }
+
+        if let [hash160, (_, Some(hash)), equal] = chunks {
+            if is_op(hash160, OpCodes::OP_HASH160)
+                && hash.len() == 20
+                && is_op(equal, OpCodes::OP_EQUAL)
+            {
+                return template(ScriptType::P2sh);
+            }
+        }
+
+        if is_multisig(chunks) {
+            return template(ScriptType::Multisig);
+        }
+
+        match chunks {
+            [op_false, op_return, ..]
+                if is_op(op_false, OpCodes::OP_0) && is_op(op_return, OpCodes::OP_RETURN) =>
+            {
+                return template(ScriptType::OpFalseOpReturn);
+            }
+            [op_return, ..] if is_op(op_return, OpCodes::OP_RETURN) => {
+                return template(ScriptType::OpReturn);
+            }
+            _ => {}
+        }
+
+        if has_inscription(chunks) {
+            // 1Sat Ordinals put the envelope before or after a regular P2PKH script
+            let hash = chunks.get(..5).and_then(p2pkh_hash).or_else(|| {
+                chunks
+                    .get(chunks.len().saturating_sub(5)..)
+                    .and_then(p2pkh_hash)
+            });
+
+            return match hash {
+                Some(hash) => Self {
+                    pubkey_hash: Some(hash.to_vec()),
+                    ..template(ScriptType::OneSatP2pkhWithInscription)
+                },
+                None => template(ScriptType::OrdinalInscription),
+            };
+        }
+
+        template(ScriptType::Nonstandard)
+    }
+
+    /// Key material of the types that have an address
+    pub fn key(&self) -> Option<AddressKey> {
+        match (&self.pubkey_hash, &self.pubkey) {
+            (Some(hash), _) => AddressKey::from_hash160(hash),
+            (None, Some(pubkey)) => AddressKey::from_pubkey(pubkey),
+            (None, None) => None,
+        }
+    }
+
+    /// P2PKH address for the types that have one
+    pub fn address(&self, network: Network) -> Option<String> {
+        self.key().and_then(|key| key.address(network))
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Raw key material an address is derived from
+pub struct AddressKey {
+    pub hash160: Vec<u8>,
+    /// Public key, when the hash was derived from one
+    pub pubkey: Option<Vec<u8>>,
+}
+
+impl AddressKey {
+    pub fn from_hash160(hash160: &[u8]) -> Option<Self> {
+        (hash160.len() == 20).then(|| Self {
+            hash160: hash160.to_vec(),
+            pubkey: None,
+        })
+    }
+
+    pub fn from_pubkey(pubkey: &[u8]) -> Option<Self> {
+        PublicKey::from_bytes(pubkey).ok()?;
+
+        Some(Self {
+            hash160: Hash::hash_160(pubkey).to_bytes(),
+            pubkey: Some(pubkey.to_vec()),
+        })
+    }
+
+    pub fn address(&self, network: Network) -> Option<String> {
+        network.p2pkh_address(&self.hash160)
+    }
+}
+
+/// Borrowed script chunk: its opcode and push data (if any)
+pub type ScriptChunk<'a> = (u8, Option<&'a [u8]>);
+
+/// Borrows a flattened ScriptBit as a chunk
+fn script_chunk(bit: &ScriptBit) -> ScriptChunk {
+    match bit {
+        ScriptBit::OpCode(op) => (*op as u8, None),
+        ScriptBit::Push(data) => (data.len() as u8, Some(data.as_slice())),
+        ScriptBit::PushData(code, data) => (*code as u8, Some(data.as_slice())),
+        // Conditionals are flattened beforehand
+        _ => (0xff, None),
+    }
+}
+
+fn is_op(chunk: &ScriptChunk, code: OpCodes) -> bool {
+    matches!(chunk, (op, None) if *op == code as u8)
+}
+
+/// `OP_1`..`OP_16` as a number
+fn small_int(chunk: &ScriptChunk) -> Option<usize> {
+    match chunk {
+        (op @ 0x51..=0x60, None) => Some((op - 0x50) as usize),
+        _ => None,
+    }
+}
+
+/// `OP_DUP OP_HASH160 <20 bytes> OP_EQUALVERIFY OP_CHECKSIG`
+fn p2pkh_hash<'a>(chunks: &[ScriptChunk<'a>]) -> Option<&'a [u8]> {
+    match chunks {
+        [dup, hash160, (_, Some(hash)), equalverify, checksig]
+            if is_op(dup, OpCodes::OP_DUP)
+                && is_op(hash160, OpCodes::OP_HASH160)
+                && is_op(equalverify, OpCodes::OP_EQUALVERIFY)
+                && is_op(checksig, OpCodes::OP_CHECKSIG)
+                && hash.len() == 20 =>
+        {
+            Some(*hash)
+        }
+        _ => None,
+    }
+}
+
+/// `<m> <pubkey>... <n> OP_CHECKMULTISIG`
+fn is_multisig(chunks: &[ScriptChunk]) -> bool {
+    let [m, pubkeys @ .., n, checkmultisig] = chunks else {
+        return false;
+    };
+
+    match (small_int(m), small_int(n)) {
+        (Some(m), Some(n)) => {
+            is_op(checkmultisig, OpCodes::OP_CHECKMULTISIG)
+                && m <= n
+                && n == pubkeys.len()
+                && pubkeys
+                    .iter()
+                    .all(|(_, key)| key.is_some_and(|key| key.len() == 33 || key.len() == 65))
+        }
+        _ => false,
+    }
+}
+
+/// `OP_0 OP_IF "ord" ... OP_ENDIF`
+fn has_inscription(chunks: &[ScriptChunk]) -> bool {
+    chunks.windows(3).any(|window| {
+        is_op(&window[0], OpCodes::OP_0)
+            && is_op(&window[1], OpCodes::OP_IF)
+            && window[2].1 == Some(b"ord".as_slice())
+    })
+}
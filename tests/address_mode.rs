@@ -0,0 +1,52 @@
+mod common;
+
+use bpu::{AddressMode, ParseConfig, BPU};
+use common::{p2pkh, push, raw_tx};
+
+/// Output 0 has an address, output 1 does not
+fn json(address_mode: Option<AddressMode>) -> serde_json::Value {
+    let raw = raw_tx(&[vec![]], &[(1, p2pkh([0x22; 20])), (0, push(b"data"))]);
+    let parse_config = ParseConfig {
+        address_mode: address_mode.unwrap_or_default(),
+        ..Default::default()
+    };
+
+    serde_json::to_value(BPU::from_bytes(&raw, parse_config).unwrap()).unwrap()
+}
+
+#[test]
+fn defaults_to_the_legacy_string() {
+    assert_eq!(AddressMode::default(), AddressMode::Legacy);
+
+    let json = json(None);
+    assert!(json["out"][0]["e"]["a"].as_str().unwrap().starts_with('1'));
+    assert_eq!(json["out"][1]["e"]["a"], "false");
+    assert_eq!(json["in"][0]["e"]["a"], "false");
+}
+
+#[test]
+fn omit_drops_missing_addresses() {
+    let json = json(Some(AddressMode::Omit));
+
+    assert!(json["out"][0]["e"]["a"].is_string());
+    assert!(json["out"][1]["e"].get("a").is_none());
+    assert!(json["in"][0]["e"].get("a").is_none());
+}
+
+#[test]
+fn false_emits_json_false() {
+    let json = json(Some(AddressMode::False));
+
+    assert!(json["out"][0]["e"]["a"].is_string());
+    assert_eq!(json["out"][1]["e"]["a"], false);
+    assert_eq!(json["in"][0]["e"]["a"], false);
+}
+
+#[test]
+fn parses_address_mode_from_config() {
+    let parse_config: ParseConfig = serde_json::from_str(r#"{"address_mode":"omit"}"#).unwrap();
+    assert_eq!(parse_config.address_mode, AddressMode::Omit);
+
+    let parse_config: ParseConfig = serde_json::from_str("{}").unwrap();
+    assert_eq!(parse_config.address_mode, AddressMode::Legacy);
+}
@@ -0,0 +1,44 @@
+mod common;
+
+use bpu::{ParseConfig, BPU};
+use common::{p2pkh, push, raw_tx};
+
+fn txs() -> Vec<Vec<u8>> {
+    (0..16u8)
+        .map(|n| raw_tx(&[push(&[n])], &[(n as u64, p2pkh([n; 20]))]))
+        .collect()
+}
+
+#[test]
+fn returns_results_in_input_order() {
+    let txs = txs();
+    let results = BPU::from_bytes_batch(&txs, &ParseConfig::default());
+
+    assert_eq!(results.len(), txs.len());
+    for (result, raw) in results.iter().zip(&txs) {
+        let expected = BPU::from_bytes(raw, ParseConfig::default()).unwrap();
+        assert_eq!(
+            result.as_ref().unwrap().tx.h.as_deref(),
+            expected.tx.h.as_deref()
+        );
+    }
+}
+
+#[test]
+fn keeps_errors_per_transaction() {
+    let mut txs = txs();
+    txs[5].truncate(10);
+
+    let results = BPU::from_bytes_batch(&txs, &ParseConfig::default());
+
+    assert_eq!(results.len(), txs.len());
+    for (i, result) in results.iter().enumerate() {
+        match i {
+            5 => assert!(result.is_err()),
+            _ => assert_eq!(
+                result.as_ref().unwrap().r#out[0].e.as_ref().unwrap().v,
+                Some(i as u64)
+            ),
+        }
+    }
+}
@@ -0,0 +1,117 @@
+mod common;
+
+use bpu::{BitcomRegistry, BlobConfig, BlobStore, Bob, ParseConfig, Result, BPU};
+use bsv::Hash;
+use common::{push, raw_tx};
+use std::sync::Arc;
+
+const B: &[u8] = b"19HxigV4QyBv3tHpQVcUEQyq1pzZVdoAut";
+const MAP: &[u8] = b"1PuQa7K62MiKCtssSLKy1kh56WWU7MtUR5";
+
+/// Hashes blobs without keeping them
+struct HashOnlyStore;
+
+impl BlobStore for HashOnlyStore {
+    fn put(&self, bytes: &[u8]) -> Result<String> {
+        Ok(Hash::sha_256(bytes).to_hex())
+    }
+}
+
+/// `OP_0 OP_RETURN` followed by `pushes`
+fn op_return(pushes: &[&[u8]]) -> Vec<u8> {
+    let mut script = vec![0x00, 0x6a];
+    for data in pushes {
+        script.extend(push(data));
+    }
+    script
+}
+
+fn labels(parse_config: ParseConfig, script: Vec<u8>) -> Vec<Option<String>> {
+    let raw = raw_tx(&[vec![]], &[(0, script)]);
+    let bpu = BPU::from_bytes(&raw, parse_config).unwrap();
+
+    bpu.r#out[0]
+        .tape
+        .iter()
+        .map(|tape| tape.protocol.clone())
+        .collect()
+}
+
+fn bob_config(registry: &Arc<BitcomRegistry>) -> ParseConfig {
+    ParseConfig {
+        bitcom: Some(registry.clone()),
+        ..Bob::config()
+    }
+}
+
+#[test]
+fn labels_tapes_by_their_prefix() {
+    let registry = Arc::new(BitcomRegistry::new());
+    let script = op_return(&[
+        B,
+        b"hello",
+        b"text/plain",
+        b"|",
+        MAP,
+        b"SET",
+        b"app",
+        b"test",
+    ]);
+
+    assert_eq!(
+        labels(bob_config(&registry), script),
+        vec![None, Some("B".to_string()), None, Some("MAP".to_string())]
+    );
+}
+
+#[test]
+fn skips_leading_opcodes() {
+    let parse_config = ParseConfig {
+        bitcom: Some(Arc::new(BitcomRegistry::new())),
+        ..Default::default()
+    };
+
+    assert_eq!(
+        labels(parse_config, op_return(&[B, b"hello"])),
+        vec![Some("B".to_string())]
+    );
+}
+
+#[test]
+fn labels_runtime_registered_prefixes() {
+    let registry = Arc::new(BitcomRegistry::new());
+    let script = || op_return(&[b"1MyProtocol", b"data"]);
+
+    assert_eq!(labels(bob_config(&registry), script()), vec![None, None]);
+
+    registry.register("1MyProtocol", "MINE", None);
+    assert_eq!(
+        labels(bob_config(&registry), script()),
+        vec![None, Some("MINE".to_string())]
+    );
+
+    registry.unregister("1MyProtocol");
+    assert_eq!(labels(bob_config(&registry), script()), vec![None, None]);
+}
+
+#[test]
+fn uses_only_the_first_push_as_prefix() {
+    let registry = Arc::new(BitcomRegistry::new());
+    let parse_config = ParseConfig {
+        blob: Some(BlobConfig {
+            store: Arc::new(HashOnlyStore),
+            limit: 8,
+        }),
+        ..bob_config(&registry)
+    };
+
+    // The offloaded first push has no inline prefix, so the later "$" must not label the tape
+    assert_eq!(
+        labels(parse_config, op_return(&[b"a long first push", b"$"])),
+        vec![None, None]
+    );
+    assert_eq!(
+        labels(bob_config(&registry), op_return(&[b"data", B])),
+        vec![None, None]
+    );
+}
@@ -0,0 +1,159 @@
+mod common;
+
+use bpu::{BlkFiles, Network, ParseConfig};
+use bsv::Hash;
+use common::{block, header, p2pkh, push, raw_tx};
+use std::fs;
+use std::path::PathBuf;
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("bpu-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Block hash in display order
+fn display_hash(header: &[u8]) -> String {
+    let mut hash = Hash::sha_256d(header).to_bytes();
+    hash.reverse();
+    hex::encode(hash)
+}
+
+fn record(network: Network, block: &[u8]) -> Vec<u8> {
+    let mut record = network.disk_magic().to_vec();
+    record.extend_from_slice(&(block.len() as u32).to_le_bytes());
+    record.extend_from_slice(block);
+    record
+}
+
+#[test]
+fn orders_the_longest_chain_by_height() {
+    let network = Network::Regtest;
+    let coinbase = raw_tx(&[push(b"genesis")], &[(50, p2pkh([0x22; 20]))]);
+
+    let genesis = header(&[0; 32], 1, 0);
+    let a = header(&Hash::sha_256d(&genesis).to_bytes(), 2, 0);
+    let b = header(&Hash::sha_256d(&a).to_bytes(), 3, 0);
+    let stale = header(&Hash::sha_256d(&a).to_bytes(), 3, 1);
+    let c = header(&Hash::sha_256d(&b).to_bytes(), 4, 0);
+
+    let dir = temp_dir("blk");
+
+    // Stored out of order, with a stale sibling of `b` and preallocated zero padding
+    let first = [
+        record(network, &block(&c, &[])),
+        record(network, &block(&stale, &[])),
+        record(network, &block(&a, &[])),
+    ]
+    .concat();
+    let mut second = [
+        record(network, &block(&b, &[])),
+        record(network, &block(&genesis, &[coinbase.clone()])),
+    ]
+    .concat();
+    second.extend_from_slice(&[0; 64]);
+
+    fs::write(dir.join("blk00000.dat"), first).unwrap();
+    fs::write(dir.join("blk00001.dat"), second).unwrap();
+    fs::write(dir.join("rev00000.dat"), [0xff; 16]).unwrap();
+
+    let files = BlkFiles::new(&dir, network);
+    assert_eq!(files.records().unwrap().len(), 5);
+
+    let chain = files.records_by_height(0).unwrap();
+    let hashes: Vec<(u32, String)> = chain
+        .iter()
+        .map(|(height, record)| (*height, record.header.hash.clone()))
+        .collect();
+
+    assert_eq!(
+        hashes,
+        vec![
+            (0, display_hash(&genesis)),
+            (1, display_hash(&a)),
+            (2, display_hash(&b)),
+            (3, display_hash(&c)),
+        ]
+    );
+
+    let mut reader = files
+        .open(&chain[0].1, chain[0].0, ParseConfig::default())
+        .unwrap();
+    assert_eq!(reader.tx_count(), 1);
+    let tx = reader.next().unwrap().unwrap();
+    assert_eq!(tx.blk.unwrap().height(), 0);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn rejects_another_networks_magic() {
+    let dir = temp_dir("blk-magic");
+    let genesis = block(&header(&[0; 32], 1, 0), &[]);
+
+    fs::write(dir.join("blk00000.dat"), record(Network::Mainnet, &genesis)).unwrap();
+
+    assert!(BlkFiles::new(&dir, Network::Testnet).records().is_err());
+    assert_eq!(
+        BlkFiles::new(&dir, Network::Mainnet)
+            .records()
+            .unwrap()
+            .len(),
+        1
+    );
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn reads_extended_record_sizes() {
+    let network = Network::Mainnet;
+    let dir = temp_dir("blk-large");
+    let coinbase = raw_tx(&[push(b"large")], &[(50, p2pkh([0x22; 20]))]);
+    let genesis = block(&header(&[0; 32], 1, 0), &[coinbase]);
+
+    // Size prefix of a block of 4 GiB or more: u32::MAX, then the u64 size
+    let mut large = network.disk_magic().to_vec();
+    large.extend_from_slice(&u32::MAX.to_le_bytes());
+    large.extend_from_slice(&(genesis.len() as u64).to_le_bytes());
+    large.extend_from_slice(&genesis);
+
+    fs::write(
+        dir.join("blk00000.dat"),
+        [
+            large,
+            record(network, &block(&header(&[0x55; 32], 2, 0), &[])),
+        ]
+        .concat(),
+    )
+    .unwrap();
+
+    let files = BlkFiles::new(&dir, network);
+    let records = files.records().unwrap();
+    assert_eq!(records.len(), 2);
+    assert_eq!(records[0].offset, 16);
+    assert_eq!(records[0].size, genesis.len() as u64);
+
+    let mut reader = files.open(&records[0], 0, ParseConfig::default()).unwrap();
+    assert!(reader.next().unwrap().is_ok());
+    assert!(reader.next().is_none());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn rejects_sizes_past_the_end_of_the_file() {
+    let network = Network::Mainnet;
+    let dir = temp_dir("blk-truncated");
+
+    let mut record = network.disk_magic().to_vec();
+    record.extend_from_slice(&u32::MAX.to_le_bytes());
+    record.extend_from_slice(&u64::MAX.to_le_bytes());
+    record.extend_from_slice(&block(&header(&[0; 32], 1, 0), &[]));
+    fs::write(dir.join("blk00000.dat"), record).unwrap();
+
+    assert!(BlkFiles::new(&dir, network).records().is_err());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
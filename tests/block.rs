@@ -0,0 +1,115 @@
+mod common;
+
+use bpu::{parse_block, Block, BlockHeader, BlockReader, Bob, ParseConfig, BPU};
+use bsv::Hash;
+use common::{block, header, p2pkh, push, raw_tx};
+
+fn txs() -> Vec<Vec<u8>> {
+    vec![
+        raw_tx(
+            &[push(&[0x03, 0x64, 0x00, 0x00])],
+            &[(50, p2pkh([0x22; 20]))],
+        ),
+        raw_tx(&[push(b"sig")], &[(0, vec![0x00, 0x6a, 0x01, 0x7c])]),
+    ]
+}
+
+fn txid(raw: &[u8]) -> String {
+    BPU::from_bytes(raw, ParseConfig::default())
+        .unwrap()
+        .tx
+        .h
+        .unwrap()
+}
+
+#[test]
+fn parses_header_fields() {
+    let prev = [0x44; 32];
+    let raw = header(&prev, 1_600_000_000, 42);
+    let header = BlockHeader::from_bytes(&raw).unwrap();
+
+    let mut hash = Hash::sha_256d(&raw).to_bytes();
+    hash.reverse();
+
+    assert_eq!(header.hash, hex::encode(hash));
+    assert_eq!(header.version, 1);
+    assert_eq!(header.prev_hash, hex::encode(prev));
+    assert_eq!(header.merkle_root, hex::encode([0x33; 32]));
+    assert_eq!(header.time, 1_600_000_000);
+    assert_eq!(header.bits, 0x1d00ffff);
+    assert_eq!(header.nonce, 42);
+
+    assert!(BlockHeader::from_bytes(&raw[..79]).is_err());
+}
+
+#[test]
+fn parses_every_transaction_with_its_position() {
+    let txs = txs();
+    let raw = block(&header(&[0; 32], 1_600_000_000, 7), &txs);
+
+    let results = parse_block(&raw, 100, &ParseConfig::default()).unwrap();
+    assert_eq!(results.len(), 2);
+
+    for (position, (result, tx)) in results.iter().zip(&txs).enumerate() {
+        let blk = result.blk.as_ref().unwrap();
+        assert_eq!(blk.height(), 100);
+        assert_eq!(blk.time(), 1_600_000_000);
+        assert_eq!(blk.position(), Some(position as u32));
+        assert_eq!(blk.header().unwrap().nonce, 7);
+        assert_eq!(result.tx.h.as_deref(), Some(txid(tx).as_str()));
+    }
+
+    let mut trailing = raw.clone();
+    trailing.push(0);
+    assert!(parse_block(&trailing, 100, &ParseConfig::default()).is_err());
+}
+
+#[test]
+fn streams_transactions() {
+    let txs = txs();
+    let raw = block(&header(&[0; 32], 1_600_000_000, 7), &txs);
+
+    let mut reader = BlockReader::new(raw.as_slice(), 5, ParseConfig::default()).unwrap();
+    assert_eq!(reader.tx_count(), 2);
+    assert_eq!(reader.header().nonce, 7);
+
+    let first = reader.next().unwrap().unwrap();
+    assert_eq!(first.tx.h, Some(txid(&txs[0])));
+
+    let second = reader.next().unwrap().unwrap();
+    assert_eq!(second.blk.unwrap().position(), Some(1));
+
+    assert!(reader.next().is_none());
+}
+
+#[test]
+fn streams_bob_transactions_like_bob() {
+    let txs = txs();
+    let header = header(&[0; 32], 1_600_000_000, 7);
+    let raw = block(&header, &txs);
+
+    let reader = BlockReader::bob(raw.as_slice(), 5).unwrap();
+    for (position, (result, tx)) in reader.zip(&txs).enumerate() {
+        let blk = Block::from_header(&header, 5)
+            .unwrap()
+            .with_position(position as u32);
+        let expected = Bob::from_bytes(tx, Some(blk), None).unwrap();
+
+        let json = serde_json::to_value(result.unwrap()).unwrap();
+        assert_eq!(json, serde_json::to_value(expected).unwrap());
+        assert!(json.get("lock").is_none());
+        assert!(json["in"][0].get("seq").is_none());
+    }
+}
+
+#[test]
+fn stops_after_a_truncated_transaction() {
+    let txs = txs();
+    let mut raw = block(&header(&[0; 32], 1_600_000_000, 7), &txs);
+    raw.truncate(raw.len() - 1);
+
+    let mut reader = BlockReader::new(raw.as_slice(), 5, ParseConfig::default()).unwrap();
+    assert!(reader.next().unwrap().is_ok());
+    assert!(reader.next().unwrap().is_err());
+    assert!(reader.next().is_none());
+}
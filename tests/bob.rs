@@ -0,0 +1,27 @@
+use bpu::Bob;
+
+const RAW_TX: &str = include_str!("fixtures/bob_readme.hex");
+const BOB_JSON: &str = include_str!("fixtures/bob_readme.json");
+
+#[test]
+fn matches_readme_bob_output() {
+    let bob = Bob::from_raw_tx(RAW_TX.trim(), None, None).unwrap();
+
+    assert_eq!(
+        serde_json::to_value(&bob).unwrap(),
+        serde_json::from_str::<serde_json::Value>(BOB_JSON).unwrap()
+    );
+}
+
+#[test]
+fn keeps_each_delimiter_as_its_own_tape() {
+    let bob = Bob::from_raw_tx(RAW_TX.trim(), None, None).unwrap();
+    let tapes = &bob.r#out[0].tape;
+
+    assert_eq!(
+        tapes.iter().map(|tape| tape.i).collect::<Vec<_>>(),
+        vec![0, 1, 2, 3, 4, 5]
+    );
+    assert_eq!(tapes[2].cell[0].s.as_deref(), Some("|"));
+    assert_eq!(tapes[4].cell[0].s.as_deref(), Some("|"));
+}
@@ -0,0 +1,36 @@
+use bpu::{Bob, BpuRef, ParseConfig, BPU};
+
+const RAW_TX: &str = include_str!("fixtures/bob_readme.hex");
+
+fn assert_same_json(parse_config: ParseConfig) {
+    let raw = hex::decode(RAW_TX.trim()).unwrap();
+
+    let owned = BPU::from_bytes(&raw, parse_config.clone()).unwrap();
+    let borrowed = BpuRef::parse(&raw, &parse_config).unwrap();
+
+    assert_eq!(
+        serde_json::to_value(&borrowed).unwrap(),
+        serde_json::to_value(&owned).unwrap()
+    );
+}
+
+#[test]
+fn matches_bpu_with_default_config() {
+    assert_same_json(ParseConfig::default());
+}
+
+#[test]
+fn matches_bpu_with_push_info() {
+    assert_same_json(ParseConfig {
+        push_info: true,
+        ..Default::default()
+    });
+}
+
+#[test]
+fn matches_bpu_with_split_rules() {
+    assert_same_json(ParseConfig {
+        large_push_threshold: None,
+        ..Bob::config()
+    });
+}
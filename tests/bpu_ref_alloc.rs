@@ -0,0 +1,63 @@
+mod common;
+
+use bpu::{BpuRef, ParseConfig, ScriptType};
+use common::{inscription, raw_tx};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Counts the bytes allocated through the global allocator
+struct Counting;
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATED.fetch_add(layout.size(), Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATED.fetch_add(new_size, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+#[test]
+fn does_not_copy_large_inscriptions() {
+    // 4 MB body in 64 KB pushes (the largest OP_PUSHDATA2 push)
+    let body = vec![0x42; 0xffff];
+    let parts: Vec<&[u8]> = (0..64).map(|_| body.as_slice()).collect();
+    let raw = raw_tx(&[vec![]], &[(1, inscription("image/png", &parts))]);
+    let parse_config = ParseConfig::default();
+
+    let before = ALLOCATED.load(Ordering::Relaxed);
+    let bpu = BpuRef::parse(&raw, &parse_config).unwrap();
+    let allocated = ALLOCATED.load(Ordering::Relaxed) - before;
+
+    assert!(
+        allocated < 64 * 1024,
+        "parsing allocated {} bytes for a {} byte transaction",
+        allocated,
+        raw.len()
+    );
+
+    let output = &bpu.r#out[0];
+    assert_eq!(
+        output.script_type,
+        Some(ScriptType::OneSatP2pkhWithInscription)
+    );
+
+    let raw_range = raw.as_ptr_range();
+    for cell in output.tape.iter().flat_map(|tape| &tape.cell) {
+        if let Some(bytes) = cell.b {
+            assert!(raw_range.contains(&bytes.as_ptr()));
+        }
+    }
+}
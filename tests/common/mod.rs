@@ -0,0 +1,106 @@
+#![allow(dead_code)]
+
+/// Script push of `bytes` using the smallest push opcode
+pub fn push(bytes: &[u8]) -> Vec<u8> {
+    let mut script = match bytes.len() {
+        len @ 0..=0x4b => vec![len as u8],
+        len @ 0x4c..=0xff => vec![0x4c, len as u8],
+        len => {
+            let mut prefix = vec![0x4d];
+            prefix.extend_from_slice(&(len as u16).to_le_bytes());
+            prefix
+        }
+    };
+    script.extend_from_slice(bytes);
+    script
+}
+
+pub fn varint(n: usize) -> Vec<u8> {
+    match n {
+        0..=0xfc => vec![n as u8],
+        0xfd..=0xffff => {
+            let mut bytes = vec![0xfd];
+            bytes.extend_from_slice(&(n as u16).to_le_bytes());
+            bytes
+        }
+        _ => {
+            let mut bytes = vec![0xfe];
+            bytes.extend_from_slice(&(n as u32).to_le_bytes());
+            bytes
+        }
+    }
+}
+
+/// Serialized version 1 transaction spending `inputs.len()` dummy outpoints,
+/// with one (satoshis, locking script) pair per output
+pub fn raw_tx(inputs: &[Vec<u8>], outputs: &[(u64, Vec<u8>)]) -> Vec<u8> {
+    let mut raw = 1u32.to_le_bytes().to_vec();
+
+    raw.extend(varint(inputs.len()));
+    for (vout, script) in inputs.iter().enumerate() {
+        raw.extend_from_slice(&[0x11; 32]);
+        raw.extend_from_slice(&(vout as u32).to_le_bytes());
+        raw.extend(varint(script.len()));
+        raw.extend_from_slice(script);
+        raw.extend_from_slice(&u32::MAX.to_le_bytes());
+    }
+
+    raw.extend(varint(outputs.len()));
+    for (satoshis, script) in outputs {
+        raw.extend_from_slice(&satoshis.to_le_bytes());
+        raw.extend(varint(script.len()));
+        raw.extend_from_slice(script);
+    }
+
+    raw.extend_from_slice(&0u32.to_le_bytes());
+    raw
+}
+
+/// `OP_DUP OP_HASH160 <hash> OP_EQUALVERIFY OP_CHECKSIG`
+pub fn p2pkh(hash: [u8; 20]) -> Vec<u8> {
+    let mut script = vec![0x76, 0xa9];
+    script.extend(push(&hash));
+    script.extend_from_slice(&[0x88, 0xac]);
+    script
+}
+
+/// 1Sat Ordinals inscription envelope followed by a P2PKH script
+pub fn inscription(content_type: &str, body: &[&[u8]]) -> Vec<u8> {
+    let mut script = vec![0x00, 0x63];
+    script.extend(push(b"ord"));
+    script.push(0x51);
+    script.extend(push(content_type.as_bytes()));
+    script.push(0x00);
+    for part in body {
+        script.extend(push(part));
+    }
+    script.push(0x68);
+    script.extend(p2pkh([0x22; 20]));
+    script
+}
+
+/// Serialized block header on top of `prev` (a block hash in internal byte order)
+pub fn header(prev: &[u8], time: u32, nonce: u32) -> Vec<u8> {
+    let mut header = 1u32.to_le_bytes().to_vec();
+    header.extend_from_slice(prev);
+    header.extend_from_slice(&[0x33; 32]); // merkle root
+    header.extend_from_slice(&time.to_le_bytes());
+    header.extend_from_slice(&0x1d00ffffu32.to_le_bytes());
+    header.extend_from_slice(&nonce.to_le_bytes());
+    header
+}
+
+/// Serialized block with `header` and `txs`
+pub fn block(header: &[u8], txs: &[Vec<u8>]) -> Vec<u8> {
+    let mut raw = header.to_vec();
+    raw.extend(varint(txs.len()));
+    for tx in txs {
+        raw.extend_from_slice(tx);
+    }
+    raw
+}
+
+/// Compressed secp256k1 generator point, a valid public key
+pub fn pubkey() -> Vec<u8> {
+    hex::decode("0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798").unwrap()
+}
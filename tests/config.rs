@@ -0,0 +1,51 @@
+use bpu::{Include, ParseConfig};
+
+#[test]
+fn parses_readme_config() {
+    let parse_config: ParseConfig =
+        serde_json::from_str(r#"{"split":[{"token":{"s":"|"},"include":"l"}]}"#).unwrap();
+
+    assert_eq!(parse_config.split.len(), 1);
+    assert_eq!(parse_config.split[0].token.s.as_deref(), Some("|"));
+    assert!(matches!(parse_config.split[0].include, Some(Include::Left)));
+    assert!(parse_config.split[0].scope.is_none());
+    assert!(parse_config.large_push_threshold.is_none());
+}
+
+#[test]
+fn parses_token_and_include_forms() {
+    let parse_config: ParseConfig = serde_json::from_str(
+        r#"{
+            "split": [
+                {"token": {"op": 106}, "include": "l"},
+                {"token": {"ops": "OP_RETURN"}, "include": "r"},
+                {"token": {"b": "fA=="}, "include": "c"},
+                {"token": {"s": "|"}}
+            ]
+        }"#,
+    )
+    .unwrap();
+
+    let split = &parse_config.split;
+    assert_eq!(split.len(), 4);
+
+    assert_eq!(split[0].token.op, Some(106));
+    assert!(matches!(split[0].include, Some(Include::Left)));
+
+    assert_eq!(split[1].token.ops.as_deref(), Some("OP_RETURN"));
+    assert!(split[1].token.matches_op(0x6a, "OP_RETURN"));
+    assert!(matches!(split[1].include, Some(Include::Right)));
+
+    assert_eq!(split[2].token.b.as_deref(), Some(b"|".as_slice()));
+    assert!(matches!(split[2].include, Some(Include::Center)));
+
+    assert!(split[3].include.is_none());
+}
+
+#[test]
+fn rejects_unknown_include() {
+    assert!(serde_json::from_str::<ParseConfig>(
+        r#"{"split":[{"token":{"s":"|"},"include":"x"}]}"#
+    )
+    .is_err());
+}
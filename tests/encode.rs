@@ -0,0 +1,114 @@
+mod common;
+
+use bpu::{Bob, Include, ParseConfig, SplitConfig, Token, BPU};
+use common::{inscription, p2pkh, push, raw_tx};
+
+const README_TX: &str = include_str!("fixtures/bob_readme.hex");
+
+fn round_trip(raw: &[u8], parse_config: ParseConfig) -> bpu::Result<Vec<u8>> {
+    let bpu = BPU::from_bytes(raw, parse_config.clone())?;
+    Ok(hex::decode(bpu.to_raw_tx(&parse_config)?).unwrap())
+}
+
+fn discard_pipes() -> ParseConfig {
+    ParseConfig {
+        split: vec![SplitConfig {
+            include: None,
+            token: Token {
+                s: Some("|".to_string()),
+                ..Default::default()
+            },
+            scope: None,
+        }],
+        ..Default::default()
+    }
+}
+
+#[test]
+fn round_trips_readme_tx() {
+    let raw = hex::decode(README_TX.trim()).unwrap();
+
+    assert_eq!(round_trip(&raw, ParseConfig::default()).unwrap(), raw);
+    assert_eq!(round_trip(&raw, Bob::config()).unwrap(), raw);
+    assert_eq!(round_trip(&raw, discard_pipes()).unwrap(), raw);
+}
+
+#[test]
+fn restores_trailing_discarded_delimiter() {
+    let mut script = vec![0x00, 0x6a];
+    script.extend(push(b"a"));
+    script.extend(push(b"|"));
+    script.extend(push(b"b"));
+    script.extend(push(b"|"));
+    let raw = raw_tx(&[push(b"|")], &[(0, script), (1, p2pkh([0x22; 20]))]);
+
+    let bpu = BPU::from_bytes(&raw, discard_pipes()).unwrap();
+    assert_eq!(bpu.r#out[0].chunks, Some(6));
+    assert_eq!(bpu.r#out[1].chunks, None);
+    assert_eq!(bpu.r#in[0].chunks, Some(1));
+
+    assert_eq!(round_trip(&raw, discard_pipes()).unwrap(), raw);
+}
+
+#[test]
+fn round_trips_non_minimal_pushes_with_push_info() {
+    // OP_RETURN OP_PUSHDATA1 <"abc"> OP_PUSHDATA2 <"d">
+    let script = vec![0x6a, 0x4c, 0x03, b'a', b'b', b'c', 0x4d, 0x01, 0x00, b'd'];
+    let raw = raw_tx(&[push(b"sig")], &[(0, script)]);
+
+    let push_info = ParseConfig {
+        push_info: true,
+        ..Default::default()
+    };
+    assert_eq!(round_trip(&raw, push_info).unwrap(), raw);
+
+    // Without the recorded push opcodes the pushes are rebuilt minimally
+    assert!(round_trip(&raw, ParseConfig::default()).is_err());
+}
+
+#[test]
+fn round_trips_nested_branches() {
+    let mut script = inscription("text/plain", &[b"hello"]);
+    // OP_1 OP_IF <"yes"> OP_ELSE <"no"> OP_ENDIF
+    script.push(0x51);
+    script.push(0x63);
+    script.extend(push(b"yes"));
+    script.push(0x67);
+    script.extend(push(b"no"));
+    script.push(0x68);
+    let raw = raw_tx(&[push(b"sig")], &[(1, script)]);
+
+    let nested = ParseConfig {
+        nested: true,
+        ..Default::default()
+    };
+
+    let bpu = BPU::from_bytes(&raw, nested.clone()).unwrap();
+    let op_if = &bpu.r#out[0].tape[0].cell[1];
+    assert_eq!(op_if.op, Some(0x63));
+    assert!(op_if.pass.is_some());
+
+    assert_eq!(round_trip(&raw, nested).unwrap(), raw);
+    assert_eq!(round_trip(&raw, ParseConfig::default()).unwrap(), raw);
+}
+
+#[test]
+fn keeps_left_and_right_delimiters() {
+    let raw = hex::decode(README_TX.trim()).unwrap();
+
+    for include in [Include::Left, Include::Right] {
+        let parse_config = ParseConfig {
+            split: vec![SplitConfig {
+                include: Some(include),
+                token: Token {
+                    s: Some("|".to_string()),
+                    ..Default::default()
+                },
+                scope: None,
+            }],
+            ..Default::default()
+        };
+
+        assert_eq!(round_trip(&raw, parse_config).unwrap(), raw);
+    }
+}
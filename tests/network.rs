@@ -0,0 +1,46 @@
+mod common;
+
+use bpu::{Network, ParseConfig, BPU};
+use common::{p2pkh, pubkey, push, raw_tx};
+
+#[test]
+fn derives_addresses_per_network() {
+    let hash160 = hex::decode("751e76e8199196d454941c45d1b3a323f1433bd6").unwrap();
+
+    assert_eq!(
+        Network::Mainnet.p2pkh_address(&hash160).as_deref(),
+        Some("1BgGZ9tcN4rm9KBzDn7KprQz87SZ26SAMH")
+    );
+    assert_eq!(
+        Network::Testnet.p2pkh_address(&hash160).as_deref(),
+        Some("mrCDrCybB6J1vRfbwM5hemdJz73FwDBC8r")
+    );
+    assert_eq!(
+        Network::Regtest.p2pkh_address(&hash160),
+        Network::Testnet.p2pkh_address(&hash160)
+    );
+    assert!(Network::Mainnet.p2pkh_address(&hash160[..19]).is_none());
+}
+
+#[test]
+fn uses_the_configured_network_for_inputs_and_outputs() {
+    let mut unlocking = push(&[0x30; 71]);
+    unlocking.extend(push(&pubkey()));
+    let raw = raw_tx(&[unlocking], &[(1, p2pkh([0x22; 20]))]);
+
+    for network in [Network::Mainnet, Network::Testnet, Network::Regtest] {
+        let parse_config = ParseConfig {
+            network,
+            ..Default::default()
+        };
+        let json = serde_json::to_value(BPU::from_bytes(&raw, parse_config).unwrap()).unwrap();
+
+        for address in [&json["in"][0]["e"]["a"], &json["out"][0]["e"]["a"]] {
+            let first = address.as_str().unwrap().chars().next().unwrap();
+            match network {
+                Network::Mainnet => assert_eq!(first, '1'),
+                Network::Testnet | Network::Regtest => assert!(first == 'm' || first == 'n'),
+            }
+        }
+    }
+}
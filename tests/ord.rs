@@ -0,0 +1,68 @@
+mod common;
+
+use bpu::{BlobConfig, BlobStore, Ord, OrdChunk, ParseConfig, ProtocolPipeline, Result, BPU};
+use bsv::Hash;
+use common::{inscription, raw_tx};
+use std::sync::Arc;
+
+/// Hashes blobs without keeping them
+struct HashOnlyStore;
+
+impl BlobStore for HashOnlyStore {
+    fn put(&self, bytes: &[u8]) -> Result<String> {
+        Ok(Hash::sha_256(bytes).to_hex())
+    }
+}
+
+#[test]
+fn joins_body_pushes() {
+    let raw = raw_tx(
+        &[vec![]],
+        &[(1, inscription("text/plain", &[b"hello ", b"world"]))],
+    );
+
+    let bpu = Ord::from_bytes(&raw).unwrap();
+    let bmap = ProtocolPipeline::new()
+        .with(Arc::new(Ord))
+        .run(&bpu)
+        .unwrap();
+
+    assert_eq!(bmap.ord.len(), 1);
+    assert_eq!(bmap.ord[0].content_type, "text/plain");
+    assert_eq!(bmap.ord[0].data, b"hello world");
+    assert!(bmap.ord[0].chunks.is_none());
+}
+
+#[test]
+fn keeps_offloaded_body_pushes_as_hashes() {
+    let raw = raw_tx(
+        &[vec![]],
+        &[(1, inscription("text", &[b"hello wor", b"ld!"]))],
+    );
+
+    let parse_config = ParseConfig {
+        blob: Some(BlobConfig {
+            store: Arc::new(HashOnlyStore),
+            limit: 8,
+        }),
+        ..Ord::config()
+    };
+
+    let bpu = BPU::from_bytes(&raw, parse_config).unwrap();
+    let bmap = ProtocolPipeline::new()
+        .with(Arc::new(Ord))
+        .run(&bpu)
+        .unwrap();
+
+    assert_eq!(bmap.ord.len(), 1);
+    assert_eq!(bmap.ord[0].content_type, "text");
+    assert!(bmap.ord[0].data.is_empty());
+
+    match bmap.ord[0].chunks.as_deref() {
+        Some([OrdChunk::Blob(hash), OrdChunk::Data(rest)]) => {
+            assert_eq!(hash, &Hash::sha_256(b"hello wor").to_hex());
+            assert_eq!(rest, b"ld!");
+        }
+        chunks => panic!("unexpected chunks {:?}", chunks),
+    }
+}
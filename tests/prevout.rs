@@ -0,0 +1,120 @@
+mod common;
+
+use bpu::{DirPrevOuts, MemoryPrevOuts, Network, ParseConfig, PrevOut, PrevOutProvider, BPU};
+use bsv::{Script, Transaction};
+use common::{p2pkh, raw_tx};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Display hex txid of the dummy outpoints spent by `raw_tx`
+const PREV_TXID: &str = "1111111111111111111111111111111111111111111111111111111111111111";
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("bpu-prevout-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Transaction with outputs of 1000 and 2000 satoshis
+fn prev_tx() -> Vec<u8> {
+    raw_tx(
+        &[vec![]],
+        &[(1000, p2pkh([0x44; 20])), (2000, p2pkh([0x55; 20]))],
+    )
+}
+
+/// Spends outputs 0 and 1 of `PREV_TXID`
+fn spending_tx() -> Vec<u8> {
+    raw_tx(&[vec![], vec![]], &[(2500, p2pkh([0x66; 20]))])
+}
+
+fn parse(prev_outs: impl PrevOutProvider + 'static) -> BPU {
+    let parse_config = ParseConfig {
+        prev_outs: Some(Arc::new(prev_outs)),
+        ..Default::default()
+    };
+
+    BPU::from_bytes(&spending_tx(), parse_config).unwrap()
+}
+
+fn assert_filled(bpu: &BPU) {
+    let json = serde_json::to_value(bpu).unwrap();
+
+    assert_eq!(json["in"][0]["e"]["v"], 1000);
+    assert_eq!(json["in"][1]["e"]["v"], 2000);
+    assert_eq!(
+        json["in"][0]["e"]["a"].as_str(),
+        Network::Mainnet.p2pkh_address(&[0x44; 20]).as_deref()
+    );
+    assert_eq!(
+        json["in"][1]["e"]["a"].as_str(),
+        Network::Mainnet.p2pkh_address(&[0x55; 20]).as_deref()
+    );
+}
+
+#[test]
+fn fills_inputs_from_memory() {
+    let mut prev_outs = MemoryPrevOuts::new();
+    for (vout, (satoshis, hash)) in [(1000, [0x44; 20]), (2000, [0x55; 20])]
+        .into_iter()
+        .enumerate()
+    {
+        prev_outs.insert(
+            PREV_TXID,
+            vout as u32,
+            PrevOut {
+                satoshis,
+                script: Script::from_bytes(&p2pkh(hash)).unwrap(),
+            },
+        );
+    }
+
+    assert_filled(&parse(prev_outs));
+}
+
+#[test]
+fn adds_every_output_of_a_transaction() {
+    let prev = Transaction::from_bytes(&prev_tx()).unwrap();
+    let mut prev_outs = MemoryPrevOuts::new();
+    prev_outs.add_transaction(&prev).unwrap();
+
+    let txid = prev.get_id_hex().unwrap();
+    assert_eq!(
+        prev_outs.prev_out(&txid, 1).unwrap().unwrap().satoshis,
+        2000
+    );
+    assert!(prev_outs.prev_out(&txid, 2).unwrap().is_none());
+    assert!(prev_outs.prev_out(PREV_TXID, 0).unwrap().is_none());
+}
+
+#[test]
+fn reads_hex_and_raw_files() {
+    let hex_dir = temp_dir("hex");
+    fs::write(
+        hex_dir.join(PREV_TXID),
+        format!("{}\n", hex::encode(prev_tx())),
+    )
+    .unwrap();
+    assert_filled(&parse(DirPrevOuts::new(&hex_dir)));
+
+    let raw_dir = temp_dir("raw");
+    fs::write(raw_dir.join(PREV_TXID), prev_tx()).unwrap();
+    assert_filled(&parse(DirPrevOuts::new(&raw_dir)));
+
+    fs::remove_dir_all(hex_dir).unwrap();
+    fs::remove_dir_all(raw_dir).unwrap();
+}
+
+#[test]
+fn leaves_unknown_inputs_empty() {
+    let dir = temp_dir("missing");
+    let bpu = parse(DirPrevOuts::new(&dir));
+    let json = serde_json::to_value(&bpu).unwrap();
+
+    assert!(json["in"][0]["e"].get("v").is_none());
+    assert_eq!(json["in"][0]["e"]["a"], "false");
+
+    fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,44 @@
+mod common;
+
+use bpu::{read_raw_tx, read_varint, ParseConfig, BPU};
+use common::{p2pkh, push, raw_tx};
+
+#[test]
+fn reads_consecutive_transactions() {
+    let first = raw_tx(&[push(b"sig")], &[(1, p2pkh([0x22; 20]))]);
+    let second = raw_tx(&[push(b"a"), push(b"b")], &[(2, vec![0x6a; 300])]);
+
+    let mut stream = [first.clone(), second.clone(), vec![0xaa]].concat();
+    let mut reader = stream.as_slice();
+
+    assert_eq!(read_raw_tx(&mut reader).unwrap(), first);
+    assert_eq!(read_raw_tx(&mut reader).unwrap(), second);
+    assert_eq!(reader, &[0xaa]);
+
+    // A truncated transaction is an error
+    stream.truncate(first.len() - 1);
+    assert!(read_raw_tx(&mut stream.as_slice()).is_err());
+}
+
+fn varint(mut bytes: &[u8]) -> bpu::Result<u64> {
+    read_varint(&mut bytes)
+}
+
+#[test]
+fn reads_varints() {
+    assert_eq!(varint(&[0xfc]).unwrap(), 0xfc);
+    assert_eq!(varint(&[0xfd, 0x34, 0x12]).unwrap(), 0x1234);
+    assert_eq!(varint(&[0xfe, 0x78, 0x56, 0x34, 0x12]).unwrap(), 0x12345678);
+    assert_eq!(varint(&[0xff, 1, 0, 0, 0, 0, 0, 0, 0]).unwrap(), 1);
+    assert!(varint(&[0xfd, 0x34]).is_err());
+}
+
+#[test]
+fn parses_from_reader_like_from_bytes() {
+    let raw = raw_tx(&[push(b"sig")], &[(1, p2pkh([0x22; 20]))]);
+
+    let from_reader = BPU::from_reader(&mut raw.as_slice(), ParseConfig::default()).unwrap();
+    let from_bytes = BPU::from_bytes(&raw, ParseConfig::default()).unwrap();
+
+    assert_eq!(from_reader.to_string(), from_bytes.to_string());
+}
@@ -0,0 +1,87 @@
+mod common;
+
+use bpu::{ParseConfig, Scope, SplitConfig, Token, BPU};
+use common::{push, raw_tx};
+
+fn script(parts: &[&[u8]]) -> Vec<u8> {
+    parts.iter().flat_map(|part| push(part)).collect()
+}
+
+/// One input and three outputs, each with a `|` push; the last output also has one
+/// before its OP_RETURN
+fn tx() -> Vec<u8> {
+    let mut op_return = script(&[b"x", b"|"]);
+    op_return.push(0x6a);
+    op_return.extend(script(&[b"y", b"|", b"z"]));
+
+    raw_tx(
+        &[script(&[b"sig", b"|", b"key"])],
+        &[
+            (0, script(&[b"a", b"|", b"b"])),
+            (0, script(&[b"c", b"|", b"d"])),
+            (0, op_return),
+        ],
+    )
+}
+
+fn parse(scope: Scope) -> BPU {
+    let parse_config = ParseConfig {
+        split: vec![SplitConfig {
+            token: Token {
+                s: Some("|".to_string()),
+                ..Default::default()
+            },
+            include: None,
+            scope: Some(scope),
+        }],
+        ..Default::default()
+    };
+
+    BPU::from_bytes(&tx(), parse_config).unwrap()
+}
+
+fn tape_counts(xputs: &[bpu::IO]) -> Vec<usize> {
+    xputs.iter().map(|xput| xput.tape.len()).collect()
+}
+
+#[test]
+fn inputs_scope_leaves_outputs_alone() {
+    let bpu = parse(Scope::Inputs);
+
+    assert_eq!(tape_counts(&bpu.r#in), vec![2]);
+    assert_eq!(tape_counts(&bpu.r#out), vec![1, 1, 1]);
+}
+
+#[test]
+fn outputs_scope_leaves_inputs_alone() {
+    let bpu = parse(Scope::Outputs);
+
+    assert_eq!(tape_counts(&bpu.r#in), vec![1]);
+    assert_eq!(bpu.r#in[0].tape[0].cell.len(), 3);
+    assert_eq!(bpu.r#in[0].tape[0].cell[1].s.as_deref(), Some("|"));
+    assert_eq!(tape_counts(&bpu.r#out), vec![2, 2, 3]);
+}
+
+#[test]
+fn output_indices_scope_splits_listed_outputs_only() {
+    let bpu = parse(Scope::OutputIndices(vec![1]));
+
+    assert_eq!(tape_counts(&bpu.r#in), vec![1]);
+    assert_eq!(tape_counts(&bpu.r#out), vec![1, 2, 1]);
+}
+
+#[test]
+fn after_op_return_scope_leaves_earlier_pushes_alone() {
+    let bpu = parse(Scope::AfterOpReturn);
+
+    assert_eq!(tape_counts(&bpu.r#in), vec![1]);
+    assert_eq!(tape_counts(&bpu.r#out), vec![1, 1, 2]);
+
+    let first = &bpu.r#out[2].tape[0].cell;
+    assert_eq!(
+        first.iter().map(|cell| cell.ii).collect::<Vec<_>>(),
+        vec![0, 1, 2, 3]
+    );
+    assert_eq!(first[1].s.as_deref(), Some("|"));
+    assert_eq!(bpu.r#out[2].tape[1].cell[0].s.as_deref(), Some("z"));
+}
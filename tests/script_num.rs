@@ -0,0 +1,45 @@
+use bpu::{decode_script_num, decode_small_int};
+
+#[test]
+fn decodes_minimal_script_numbers() {
+    assert_eq!(decode_script_num(&[]), Some(0));
+    assert_eq!(decode_script_num(&[0x01]), Some(1));
+    assert_eq!(decode_script_num(&[0x81]), Some(-1));
+    assert_eq!(decode_script_num(&[0x7f]), Some(127));
+    assert_eq!(decode_script_num(&[0x80, 0x00]), Some(128));
+    assert_eq!(decode_script_num(&[0x80, 0x80]), Some(-128));
+    assert_eq!(decode_script_num(&[0xff, 0x00]), Some(255));
+    assert_eq!(decode_script_num(&[0x00, 0x01]), Some(256));
+}
+
+#[test]
+fn handles_eight_byte_extremes() {
+    let mut max = vec![0xff; 7];
+    max.push(0x7f);
+    assert_eq!(decode_script_num(&max), Some(i64::MAX));
+
+    let mut min = vec![0xff; 7];
+    min.push(0xff);
+    assert_eq!(decode_script_num(&min), Some(-i64::MAX));
+
+    assert_eq!(decode_script_num(&[0x01; 9]), None);
+}
+
+#[test]
+fn rejects_non_minimal_encodings() {
+    // Negative zero and zero padding
+    assert_eq!(decode_script_num(&[0x00]), None);
+    assert_eq!(decode_script_num(&[0x80]), None);
+    assert_eq!(decode_script_num(&[0x01, 0x00]), None);
+    assert_eq!(decode_script_num(&[0x01, 0x80]), None);
+}
+
+#[test]
+fn decodes_small_int_opcodes() {
+    assert_eq!(decode_small_int(0x00), Some(0));
+    assert_eq!(decode_small_int(0x4f), Some(-1));
+    assert_eq!(decode_small_int(0x51), Some(1));
+    assert_eq!(decode_small_int(0x60), Some(16));
+    assert_eq!(decode_small_int(0x50), None);
+    assert_eq!(decode_small_int(0x61), None);
+}
@@ -0,0 +1,126 @@
+mod common;
+
+use bpu::{ParseConfig, ScriptType, BPU};
+use common::{inscription, p2pkh, pubkey, push, raw_tx};
+
+fn detect(script: Vec<u8>) -> ScriptType {
+    let raw = raw_tx(&[vec![]], &[(1, script)]);
+    let bpu = BPU::from_bytes(&raw, ParseConfig::default()).unwrap();
+    bpu.r#out[0].script_type.unwrap()
+}
+
+/// `OP_0 OP_IF "ord" OP_1 "text/plain" OP_0 "hi" OP_ENDIF`
+fn envelope() -> Vec<u8> {
+    let mut script = vec![0x00, 0x63];
+    script.extend(push(b"ord"));
+    script.push(0x51);
+    script.extend(push(b"text/plain"));
+    script.push(0x00);
+    script.extend(push(b"hi"));
+    script.push(0x68);
+    script
+}
+
+#[test]
+fn detects_p2pkh() {
+    assert_eq!(detect(p2pkh([0x22; 20])), ScriptType::P2pkh);
+}
+
+#[test]
+fn detects_p2pk() {
+    let mut script = push(&pubkey());
+    script.push(0xac);
+
+    assert_eq!(detect(script), ScriptType::P2pk);
+}
+
+#[test]
+fn detects_p2sh() {
+    let mut script = vec![0xa9];
+    script.extend(push(&[0x22; 20]));
+    script.push(0x87);
+
+    assert_eq!(detect(script), ScriptType::P2sh);
+}
+
+#[test]
+fn detects_multisig_with_m_up_to_n() {
+    let multisig = |m: u8, n: u8| {
+        let mut script = vec![0x50 + m];
+        for _ in 0..n {
+            script.extend(push(&pubkey()));
+        }
+        script.extend_from_slice(&[0x50 + n, 0xae]);
+        script
+    };
+
+    assert_eq!(detect(multisig(1, 2)), ScriptType::Multisig);
+    assert_eq!(detect(multisig(2, 2)), ScriptType::Multisig);
+    assert_eq!(detect(multisig(3, 2)), ScriptType::Nonstandard);
+}
+
+#[test]
+fn detects_op_false_op_return() {
+    let mut script = vec![0x00, 0x6a];
+    script.extend(push(b"data"));
+
+    assert_eq!(detect(script), ScriptType::OpFalseOpReturn);
+}
+
+#[test]
+fn detects_op_return() {
+    let mut script = vec![0x6a];
+    script.extend(push(b"data"));
+
+    assert_eq!(detect(script), ScriptType::OpReturn);
+}
+
+#[test]
+fn detects_bare_inscription() {
+    assert_eq!(detect(envelope()), ScriptType::OrdinalInscription);
+}
+
+#[test]
+fn detects_1sat_inscription_before_or_after_p2pkh() {
+    assert_eq!(
+        detect(inscription("text/plain", &[b"hi"])),
+        ScriptType::OneSatP2pkhWithInscription
+    );
+
+    let mut script = p2pkh([0x22; 20]);
+    script.extend(envelope());
+    assert_eq!(detect(script), ScriptType::OneSatP2pkhWithInscription);
+
+    assert_eq!(
+        serde_json::to_value(ScriptType::OneSatP2pkhWithInscription).unwrap(),
+        "1sat_p2pkh_with_inscription"
+    );
+}
+
+#[test]
+fn detects_nonstandard() {
+    assert_eq!(detect(push(b"data")), ScriptType::Nonstandard);
+}
+
+#[test]
+fn derives_addresses_from_the_template() {
+    let mut p2pk = push(&pubkey());
+    p2pk.push(0xac);
+
+    let raw = raw_tx(
+        &[vec![]],
+        &[
+            (1, p2pkh([0x22; 20])),
+            (1, inscription("text/plain", &[b"hi"])),
+            (1, p2pk),
+            (1, push(b"data")),
+        ],
+    );
+    let bpu = BPU::from_bytes(&raw, ParseConfig::default()).unwrap();
+    let json = serde_json::to_value(&bpu).unwrap();
+    let address = |i: usize| json["out"][i]["e"]["a"].clone();
+
+    assert_eq!(address(0), address(1));
+    assert_eq!(address(2), "1BgGZ9tcN4rm9KBzDn7KprQz87SZ26SAMH");
+    assert_eq!(address(3), "false");
+}
@@ -0,0 +1,68 @@
+mod common;
+
+use bpu::{MemoryPrevOuts, ParseConfig, PrevOut, BPU};
+use bsv::Script;
+use common::{p2pkh, raw_tx};
+use std::sync::Arc;
+
+const PREV_TXID: &str = "1111111111111111111111111111111111111111111111111111111111111111";
+
+/// Spends outputs 0 and 1 of `PREV_TXID` into 2500 satoshis
+fn spending_tx() -> Vec<u8> {
+    raw_tx(
+        &[vec![], vec![]],
+        &[(2000, p2pkh([0x66; 20])), (500, p2pkh([0x77; 20]))],
+    )
+}
+
+/// Knows the 1000 and 2000 satoshi outputs of `PREV_TXID` at `vouts`
+fn parse(vouts: &[u32]) -> BPU {
+    let mut prev_outs = MemoryPrevOuts::new();
+    for &vout in vouts {
+        prev_outs.insert(
+            PREV_TXID,
+            vout,
+            PrevOut {
+                satoshis: 1000 * (vout as u64 + 1),
+                script: Script::from_bytes(&p2pkh([0x44; 20])).unwrap(),
+            },
+        );
+    }
+
+    let parse_config = ParseConfig {
+        prev_outs: Some(Arc::new(prev_outs)),
+        stats: true,
+        ..Default::default()
+    };
+
+    BPU::from_bytes(&spending_tx(), parse_config).unwrap()
+}
+
+#[test]
+fn computes_fee_when_every_input_is_known() {
+    let stats = parse(&[0, 1]).stats.unwrap();
+    let size = spending_tx().len();
+
+    assert_eq!(stats.size, size);
+    assert_eq!(stats.input, Some(3000));
+    assert_eq!(stats.output, Some(2500));
+    assert_eq!(stats.fee, Some(500));
+    assert_eq!(stats.fee_rate, Some(500.0 / size as f64));
+}
+
+#[test]
+fn leaves_fee_empty_when_an_input_is_missing() {
+    let stats = parse(&[0]).stats.unwrap();
+
+    assert_eq!(stats.size, spending_tx().len());
+    assert_eq!(stats.input, None);
+    assert_eq!(stats.output, Some(2500));
+    assert_eq!(stats.fee, None);
+    assert_eq!(stats.fee_rate, None);
+}
+
+#[test]
+fn omits_stats_unless_enabled() {
+    let bpu = BPU::from_bytes(&spending_tx(), ParseConfig::default()).unwrap();
+    assert!(bpu.stats.is_none());
+}